@@ -0,0 +1,87 @@
+use ggez::audio::{SoundSource, Source};
+use ggez::Context;
+
+/// Represents something that happens in the game that is accompanied by a sound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sound {
+    /// A snake has eaten a fruit.
+    Eat,
+
+    /// The game has been lost, or has ended in a draw.
+    Lose,
+
+    /// The game has been won.
+    Win,
+}
+
+impl Sound {
+    /// Every sound, in the order they are loaded in.
+    pub const ALL: [Sound; 3] = [Sound::Eat, Sound::Lose, Sound::Win];
+
+    /// The path of the file the sound is loaded from, within the resources directory.
+    fn path(&self) -> &'static str {
+        match self {
+            Sound::Eat => "/eat.wav",
+            Sound::Lose => "/lose.wav",
+            Sound::Win => "/win.wav",
+        }
+    }
+}
+
+/// Holds the sounds the game plays, any of which may be missing.
+#[derive(Default)]
+pub struct Sounds {
+    /// The sounds that could be loaded, alongside what they are played for.
+    sources: Vec<(Sound, Source)>,
+
+    /// Whether the sounds are silenced.
+    pub muted: bool,
+}
+
+impl Sounds {
+    /// Loads the sounds from the resources directory at the given volume, from 0 to 1.
+    ///
+    /// A sound that cannot be loaded is left out with a warning, so that the game can still
+    /// be played without its resources, only in silence.
+    pub fn load(context: &mut Context, volume: f32) -> Self {
+        let mut sources = Vec::with_capacity(Sound::ALL.len());
+
+        for sound in Sound::ALL {
+            match Source::new(context, sound.path()) {
+                Ok(mut source) => {
+                    source.set_volume(volume);
+                    sources.push((sound, source));
+                }
+                Err(error) => eprintln!(
+                    "Failed to load the sound '{}', so it will not be played: {}",
+                    sound.path(),
+                    error
+                ),
+            }
+        }
+
+        Self {
+            sources,
+            muted: false,
+        }
+    }
+
+    /// Plays `sound`, unless the sounds are muted or it could not be loaded.
+    pub fn play(&mut self, context: &mut Context, sound: Sound) {
+        if self.muted {
+            return;
+        }
+
+        let source = self
+            .sources
+            .iter_mut()
+            .find(|(kind, _)| *kind == sound)
+            .map(|(_, source)| source);
+
+        if let Some(source) = source {
+            if let Err(error) = source.play_detached(context) {
+                eprintln!("Failed to play the sound '{}': {}", sound.path(), error);
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::config::GameConfig;
+use crate::structs::{Direction, Snake, Vector};
+
+use std::collections::VecDeque;
+
+/// Chooses the direction the snake should move in next to reach a fruit by the shortest
+/// path, never reversing into itself or leaving the grid, and taking shortcuts around the
+/// edges when the snake wraps around them.
+///
+/// If no fruit can be reached, the snake chases the end of its own tail, which keeps a way
+/// out open for as long as possible. Failing that, it makes any move that does not kill it
+/// straight away.
+pub fn next_direction(
+    snake: &Snake,
+    blocked: &[Vec<bool>],
+    fruit_positions: &[Vector],
+    config: &GameConfig,
+) -> Direction {
+    let reverse = snake.movement_direction.opposite();
+    let directions: Vec<Direction> = Direction::ALL
+        .iter()
+        .copied()
+        .filter(|direction| *direction != reverse)
+        .collect();
+
+    if let Some(direction) = find_path(
+        &snake.head_position,
+        blocked,
+        fruit_positions,
+        config,
+        &directions,
+    ) {
+        return direction;
+    }
+
+    if let Some(tail_end) = snake.tail_positions.back() {
+        // The end of the tail moves out of the way as the snake moves, so it can be followed.
+        let mut blocked = blocked.to_vec();
+        blocked[tail_end.y][tail_end.x] = false;
+
+        // Moving onto the end of the tail straight away still counts as a crash, though.
+        let directions: Vec<Direction> = directions
+            .iter()
+            .copied()
+            .filter(|direction| {
+                config.next_position(snake.head_position, *direction) != Some(*tail_end)
+            })
+            .collect();
+
+        if let Some(direction) = find_path(
+            &snake.head_position,
+            &blocked,
+            &[*tail_end],
+            config,
+            &directions,
+        ) {
+            return direction;
+        }
+    }
+
+    directions
+        .into_iter()
+        .find(|direction| {
+            config
+                .next_position(snake.head_position, *direction)
+                .is_some_and(|position| !blocked[position.y][position.x])
+        })
+        .unwrap_or(snake.movement_direction)
+}
+
+/// Searches breadth-first for the shortest path from `start` to any of the `targets`,
+/// leaving `start` in one of the given directions.
+///
+/// Returns the direction of the first step along the path.
+fn find_path(
+    start: &Vector,
+    blocked: &[Vec<bool>],
+    targets: &[Vector],
+    config: &GameConfig,
+    directions: &[Direction],
+) -> Option<Direction> {
+    let Vector {
+        x: width,
+        y: height,
+    } = config.dimensions();
+    let mut visited = vec![vec![false; width]; height];
+    let mut queue = VecDeque::new();
+
+    visited[start.y][start.x] = true;
+
+    for direction in directions {
+        if let Some(position) = config.next_position(*start, *direction) {
+            if !blocked[position.y][position.x] {
+                visited[position.y][position.x] = true;
+                queue.push_back((position, *direction));
+            }
+        }
+    }
+
+    while let Some((position, first_direction)) = queue.pop_front() {
+        if targets.contains(&position) {
+            return Some(first_direction);
+        }
+
+        for direction in Direction::ALL {
+            if let Some(neighbour) = config.next_position(position, direction) {
+                if !blocked[neighbour.y][neighbour.x] && !visited[neighbour.y][neighbour.x] {
+                    visited[neighbour.y][neighbour.x] = true;
+                    queue.push_back((neighbour, first_direction));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_takes_the_shortest_route_to_the_fruit() {
+        let config = GameConfig {
+            width: Some(7),
+            height: Some(7),
+            ..GameConfig::default()
+        };
+        let blocked = vec![vec![false; 7]; 7];
+
+        // The fruit is three tiles to the right of the snake and four tiles up.
+        let fruit = Vector { x: 4, y: 1 };
+        let mut snake = Snake::new(Vector { x: 1, y: 5 }, Direction::Right);
+
+        let mut moves = 0;
+        while snake.head_position != fruit {
+            assert!(moves < 7, "The snake has not reached the fruit in 7 moves.");
+
+            let direction = next_direction(&snake, &blocked, &[fruit], &config);
+            let position = config
+                .next_position(snake.head_position, direction)
+                .unwrap();
+
+            snake.movement_direction = direction;
+            snake.move_head(position);
+            snake.pop_tail();
+            moves += 1;
+        }
+
+        assert_eq!(moves, 7);
+    }
+}
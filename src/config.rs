@@ -0,0 +1,217 @@
+use crate::constants::*;
+use crate::structs::{Difficulty, Direction, GameMode, Offset, PoisonEffect, Vector};
+use crate::theme::{Theme, ThemeOverrides};
+
+use serde::{Deserialize, Serialize};
+
+/// The settings a game is set up with.
+///
+/// The sizes and colours the game is drawn in and the volume are left out of the files the
+/// game is written to, as they have no bearing on how it plays, and take their default values
+/// when read back.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// The number of tiles across the grid, or `None` for the difficulty to decide.
+    pub width: Option<usize>,
+
+    /// The number of tiles down the grid, or `None` for the difficulty to decide.
+    pub height: Option<usize>,
+
+    /// How hard the game is.
+    pub difficulty: Difficulty,
+
+    /// The rules the game is played by.
+    pub mode: GameMode,
+
+    /// Whether the snakes wrap around to the opposite edge instead of crashing into it,
+    /// whatever the difficulty.
+    pub wrap_walls: bool,
+
+    /// The number of walls to scatter across the grid.
+    pub obstacle_count: usize,
+
+    /// The number of pairs of portals to scatter across the grid.
+    pub portal_count: usize,
+
+    /// The number of fruits to keep on the grid, or `None` for the difficulty to decide.
+    pub fruit_count: Option<usize>,
+
+    /// Whether some of the fruits are special ones, such as golden fruits worth more points
+    /// or fruits that hand out a power-up, rather than all of them being plain fruits.
+    pub special_fruits: bool,
+
+    /// Whether a bonus fruit shows up now and then, for a short while only.
+    pub bonus_fruit: bool,
+
+    /// The number of tail parts the snakes start out with.
+    pub initial_tail_length: usize,
+
+    /// The number of tail parts a snake grows by for every fruit it eats, other than the
+    /// bonus fruit and the fruits that shrink it.
+    pub growth_per_fruit: usize,
+
+    /// The number of fruits after which the arena closes in by another ring of walls, or 0
+    /// for the arena to stay the size of the grid.
+    pub shrink_interval: usize,
+
+    /// The number of ticks between two tiles being poisoned, or 0 for no poison.
+    pub poison_interval: usize,
+
+    /// What happens to a snake that runs into a poisoned tile.
+    pub poison_effect: PoisonEffect,
+
+    /// The number of tiles the snakes move while boosted for every tail part it costs them,
+    /// or 0 for boosting to cost nothing.
+    pub boost_tail_cost: usize,
+
+    /// The number of players, each controlling a snake of their own.
+    pub player_count: usize,
+
+    /// The highest multiplier a combo of fruits eaten in quick succession can reach.
+    pub max_combo: usize,
+
+    /// The seed the layout of the grid is generated from, or `None` for a random one.
+    pub seed: Option<u64>,
+
+    /// The time that passes between two movements of the snakes at the start of the game,
+    /// or `None` for the difficulty to decide.
+    pub milliseconds_per_frame: Option<usize>,
+
+    /// How much shorter the time between two movements gets with every fruit eaten, or
+    /// `None` for the difficulty to decide.
+    pub speed_ramp: Option<usize>,
+
+    /// The length of the sides of a tile in a window of the size the game starts out at.
+    #[serde(skip)]
+    pub tile_size: f32,
+
+    /// The width of the margin around the grid, which is also the width of its border.
+    #[serde(skip)]
+    pub border_size: f32,
+
+    /// How loud the sounds are played, from 0 to 1.
+    #[serde(skip)]
+    pub volume: f32,
+
+    /// The palette the game is drawn in.
+    #[serde(skip)]
+    pub theme: Theme,
+
+    /// The colours set in the theme file, which are laid over every theme switched to.
+    #[serde(skip)]
+    pub theme_overrides: ThemeOverrides,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            difficulty: Difficulty::Normal,
+            mode: GameMode::Classic,
+            wrap_walls: false,
+            obstacle_count: DEFAULT_WALL_COUNT,
+            portal_count: 0,
+            fruit_count: None,
+            special_fruits: false,
+            bonus_fruit: false,
+            initial_tail_length: DEFAULT_TAIL_LENGTH,
+            growth_per_fruit: DEFAULT_GROWTH_PER_FRUIT,
+            shrink_interval: 0,
+            poison_interval: 0,
+            poison_effect: PoisonEffect::Deadly,
+            boost_tail_cost: 0,
+            player_count: 1,
+            max_combo: DEFAULT_MAX_COMBO,
+            seed: None,
+            milliseconds_per_frame: None,
+            speed_ramp: None,
+            tile_size: DEFAULT_TILE_SIZE,
+            border_size: DEFAULT_BORDER_SIZE,
+            volume: DEFAULT_VOLUME,
+            theme: Theme::default(),
+            theme_overrides: ThemeOverrides::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// The dimensions of the grid.
+    pub fn dimensions(&self) -> Vector {
+        let size = self.difficulty.grid_size();
+
+        Vector {
+            x: self.width.unwrap_or(size),
+            y: self.height.unwrap_or(size),
+        }
+    }
+
+    /// The number of fruits to keep on the grid.
+    pub fn fruit_count(&self) -> usize {
+        self.fruit_count
+            .unwrap_or_else(|| self.difficulty.fruit_count())
+    }
+
+    /// Whether the snakes wrap around to the opposite edge instead of crashing into it.
+    pub fn wraps_walls(&self) -> bool {
+        self.wrap_walls || self.difficulty.wraps_walls()
+    }
+
+    /// The position one tile away from `position` in `direction`, wrapping around to the
+    /// opposite edge if the settings allow it, or `None` if the edge of the grid is in the
+    /// way.
+    pub fn next_position(&self, position: Vector, direction: Direction) -> Option<Vector> {
+        if !self.wraps_walls() {
+            return position.neighbour(direction, &self.dimensions());
+        }
+
+        Some(position.wrapping_offset(Offset::from_direction(direction), &self.dimensions()))
+    }
+
+    /// The time that passes between two movements of the snakes at the start of the game.
+    pub fn initial_milliseconds_per_frame(&self) -> usize {
+        self.milliseconds_per_frame
+            .unwrap_or_else(|| self.difficulty.initial_milliseconds_per_frame())
+    }
+
+    /// How much shorter the time between two movements gets with every fruit eaten.
+    pub fn speed_ramp(&self) -> usize {
+        self.speed_ramp
+            .unwrap_or_else(|| self.difficulty.speed_ramp())
+    }
+
+    /// Takes the settings that are left out of the files the game is written to from
+    /// `other`, such as when a game read back from a file replaces the one being played.
+    pub fn with_unrecorded_settings(self, other: &GameConfig) -> GameConfig {
+        GameConfig {
+            tile_size: other.tile_size,
+            border_size: other.border_size,
+            volume: other.volume,
+            theme: other.theme,
+            theme_overrides: other.theme_overrides,
+            ..self
+        }
+    }
+
+    /// Checks that the settings read back from a file describe a game that can be played.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let dimensions = self.dimensions();
+        let sizes = MIN_GRID_SIZE..=MAX_GRID_SIZE;
+        if !sizes.contains(&dimensions.x) || !sizes.contains(&dimensions.y) {
+            return Err(format!(
+                "The grid must be between {} and {} tiles wide and high, but is {}x{}.",
+                MIN_GRID_SIZE, MAX_GRID_SIZE, dimensions.x, dimensions.y
+            ));
+        }
+
+        if !(1..=MAX_PLAYER_COUNT).contains(&self.player_count) {
+            return Err(format!(
+                "There must be between 1 and {} players, but there are {}.",
+                MAX_PLAYER_COUNT, self.player_count
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -1,48 +1,269 @@
+use crate::structs::FruitKind;
+
 use ggez::graphics::Color;
 
-pub const BACKGROUND_COLOR: Color = Color {
-    r: 41.0 / 255.0,
-    g: 41.0 / 255.0,
-    b: 41.0 / 255.0,
-    a: 1.0,
-};
-pub const TILE_COLORS: (Color, Color) = (
-    Color {
-        r: 51.0 / 255.0,
-        g: 51.0 / 255.0,
-        b: 51.0 / 255.0,
-        a: 1.0,
-    },
-    Color {
-        r: 59.0 / 255.0,
-        g: 59.0 / 255.0,
-        b: 59.0 / 255.0,
-        a: 1.0,
-    },
-);
-pub const HEAD_COLOR: Color = TAIL_COLORS.1;
-pub const TAIL_COLORS: (Color, Color) = (
-    Color {
-        r: 12.0 / 255.0,
-        g: 185.0 / 255.0,
-        b: 45.0 / 255.0,
-        a: 1.0,
-    },
-    Color {
-        r: 19.0 / 255.0,
-        g: 138.0 / 255.0,
-        b: 54.0 / 255.0,
-        a: 1.0,
-    },
-);
-pub const FRUIT_COLOR: Color = Color {
-    r: 255.0 / 255.0,
-    g: 87.0 / 255.0,
-    b: 51.0 / 255.0,
-    a: 1.0,
-};
-
-pub const TILE_SIZE: f32 = 50.0;
-pub const BORDER_SIZE: f32 = 10.0;
-
-pub const MILLISECONDS_PER_FRAME: usize = 300;
+pub const SECOND_HEAD_COLOR: Color = Color {
+    r: 33.0 / 255.0,
+    g: 100.0 / 255.0,
+    b: 190.0 / 255.0,
+    a: 1.0,
+};
+pub const SECOND_TAIL_COLOR_HEAD: Color = Color {
+    r: 45.0 / 255.0,
+    g: 140.0 / 255.0,
+    b: 230.0 / 255.0,
+    a: 1.0,
+};
+pub const SECOND_TAIL_COLOR_TIP: Color = Color {
+    r: 20.0 / 255.0,
+    g: 55.0 / 255.0,
+    b: 110.0 / 255.0,
+    a: 1.0,
+};
+pub const GRID_LINE_COLOR: Color = Color {
+    r: 72.0 / 255.0,
+    g: 72.0 / 255.0,
+    b: 72.0 / 255.0,
+    a: 1.0,
+};
+
+pub const GOLDEN_FRUIT_COLOR: Color = Color {
+    r: 1.0,
+    g: 199.0 / 255.0,
+    b: 44.0 / 255.0,
+    a: 1.0,
+};
+pub const SHRINK_FRUIT_COLOR: Color = Color {
+    r: 168.0 / 255.0,
+    g: 92.0 / 255.0,
+    b: 219.0 / 255.0,
+    a: 1.0,
+};
+pub const SLOW_FRUIT_COLOR: Color = Color {
+    r: 72.0 / 255.0,
+    g: 163.0 / 255.0,
+    b: 1.0,
+    a: 1.0,
+};
+pub const BOOST_FRUIT_COLOR: Color = Color {
+    r: 1.0,
+    g: 140.0 / 255.0,
+    b: 20.0 / 255.0,
+    a: 1.0,
+};
+pub const SHIELD_FRUIT_COLOR: Color = Color {
+    r: 225.0 / 255.0,
+    g: 225.0 / 255.0,
+    b: 240.0 / 255.0,
+    a: 1.0,
+};
+pub const BONUS_FRUIT_COLOR: Color = Color {
+    r: 1.0,
+    g: 105.0 / 255.0,
+    b: 180.0 / 255.0,
+    a: 1.0,
+};
+pub const EYE_COLOR: Color = Color {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+pub const WALL_COLOR: Color = Color {
+    r: 112.0 / 255.0,
+    g: 84.0 / 255.0,
+    b: 62.0 / 255.0,
+    a: 1.0,
+};
+
+pub const PORTAL_COLOR: Color = Color {
+    r: 0.0,
+    g: 190.0 / 255.0,
+    b: 170.0 / 255.0,
+    a: 1.0,
+};
+
+pub const POISON_COLOR: Color = Color {
+    r: 110.0 / 255.0,
+    g: 200.0 / 255.0,
+    b: 40.0 / 255.0,
+    a: 1.0,
+};
+
+pub const TIMER_BAR_COLOR: Color = Color {
+    r: 240.0 / 255.0,
+    g: 200.0 / 255.0,
+    b: 60.0 / 255.0,
+    a: 1.0,
+};
+
+pub const DYING_COLOR: Color = Color {
+    r: 220.0 / 255.0,
+    g: 40.0 / 255.0,
+    b: 40.0 / 255.0,
+    a: 1.0,
+};
+
+pub const OVERLAY_COLOR: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.6,
+};
+pub const PANEL_COLOR: Color = Color {
+    r: 31.0 / 255.0,
+    g: 31.0 / 255.0,
+    b: 31.0 / 255.0,
+    a: 0.9,
+};
+/// The colour of the bar along the top of the window that shows how the game is going.
+pub const HUD_COLOR: Color = Color {
+    r: 22.0 / 255.0,
+    g: 22.0 / 255.0,
+    b: 22.0 / 255.0,
+    a: 1.0,
+};
+/// The colour the combo multiplier flashes in when it goes up.
+pub const COMBO_COLOR: Color = Color {
+    r: 1.0,
+    g: 210.0 / 255.0,
+    b: 60.0 / 255.0,
+    a: 1.0,
+};
+pub const TEXT_COLOR: Color = Color {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+pub const DEFAULT_TILE_SIZE: f32 = 50.0;
+pub const MIN_TILE_SIZE: usize = 10;
+pub const MAX_TILE_SIZE: usize = 100;
+pub const DEFAULT_BORDER_SIZE: f32 = 10.0;
+pub const MAX_BORDER_SIZE: usize = 50;
+
+/// The volume the sounds are played at, from 0 to 1.
+pub const DEFAULT_VOLUME: f32 = 0.5;
+pub const GRID_LINE_WIDTH: f32 = 1.0;
+pub const HUD_HEIGHT: f32 = 40.0;
+/// The largest window the game opens in, which the tiles are scaled down to fit into on
+/// large grids, so that the window does not reach off the screen.
+pub const MAX_WINDOW_WIDTH: f32 = 1280.0;
+pub const MAX_WINDOW_HEIGHT: f32 = 960.0;
+/// The smallest the window can be resized to, below which the tiles and the text of the HUD
+/// would be too small to make out.
+pub const MIN_WINDOW_WIDTH: f32 = 320.0;
+pub const MIN_WINDOW_HEIGHT: f32 = 240.0;
+/// The height of the bar along the bottom of the HUD that shows the time left to eat the
+/// next fruit in.
+pub const TIMER_BAR_HEIGHT: f32 = 4.0;
+/// The distance from the heads of the snakes, counted in steps along the grid, within which
+/// tiles can be seen through the fog of war.
+pub const FOG_RADIUS: usize = 3;
+/// How much of the tiles just beyond [`FOG_RADIUS`] the fog of war hides, from 0 to 1.
+pub const FOG_EDGE_OPACITY: f32 = 0.6;
+
+pub const MIN_GRID_SIZE: usize = 5;
+pub const MAX_GRID_SIZE: usize = 50;
+
+pub const MAX_FRUIT_COUNT: usize = 10;
+pub const DEFAULT_WALL_COUNT: usize = 0;
+pub const MAX_WALL_COUNT: usize = 20;
+pub const MAX_PORTAL_COUNT: usize = 4;
+pub const MAX_PLAYER_COUNT: usize = 2;
+pub const DEFAULT_TAIL_LENGTH: usize = 0;
+pub const MAX_TAIL_LENGTH: usize = 10;
+/// The number of tail parts a snake grows by for every ordinary fruit it eats.
+pub const DEFAULT_GROWTH_PER_FRUIT: usize = 1;
+pub const MAX_GROWTH_PER_FRUIT: usize = 10;
+pub const MAX_SHRINK_INTERVAL: usize = 20;
+/// The smallest width and height the arena can close in to before the game is won.
+pub const MIN_ARENA_SIZE: usize = 3;
+
+/// The relative likelihood of each kind of fruit being placed.
+pub const FRUIT_KIND_WEIGHTS: [(FruitKind, usize); 6] = [
+    (FruitKind::Normal, 16),
+    (FruitKind::Golden, 2),
+    (FruitKind::Shrink, 1),
+    (FruitKind::Slow, 1),
+    (FruitKind::Boost, 1),
+    (FruitKind::Shield, 1),
+];
+pub const GOLDEN_FRUIT_SCORE: usize = 5;
+/// The number of tail parts a shrink fruit takes off, or as many as the tail has if fewer.
+pub const SHRINK_FRUIT_LENGTH: usize = 2;
+/// The number of ticks for which a slow fruit slows the snake down.
+pub const SLOW_FRUIT_DURATION: usize = 20;
+/// The number of ticks for which a boost fruit speeds the snake up.
+pub const BOOST_FRUIT_DURATION: usize = 20;
+/// The number of ticks for which a shield fruit keeps the snake from crashing.
+pub const SHIELD_FRUIT_DURATION: usize = 30;
+
+/// The number of ticks a bonus fruit stays on the grid before disappearing.
+pub const BONUS_FRUIT_DURATION: usize = 25;
+/// The number of ticks between a bonus fruit disappearing and the next one appearing.
+pub const BONUS_FRUIT_COOLDOWN: usize = 60;
+pub const BONUS_FRUIT_SCORE: usize = 5;
+pub const BONUS_FRUIT_GROWTH: usize = 3;
+
+pub const MAX_POISON_INTERVAL: usize = 100;
+/// The number of ticks a poisoned tile stays poisoned for.
+pub const POISON_DURATION: usize = 40;
+/// The number of ticks before a poisoned tile clears up over which it fades away.
+pub const POISON_FADE_TICKS: usize = 10;
+/// The number of tail parts a snake loses running into poison that shrinks it.
+pub const POISON_SHRINK_LENGTH: usize = 2;
+
+/// The number of scores kept on the scoreboard.
+pub const SCOREBOARD_LENGTH: usize = 10;
+/// The number of letters a name on the scoreboard is made up of.
+pub const INITIALS_LENGTH: usize = 3;
+
+pub const DEFAULT_TIME_ATTACK_SECONDS: usize = 120;
+pub const MAX_TIME_ATTACK_SECONDS: usize = 600;
+/// The number of seconds every fruit eaten adds to a game of time attack.
+pub const DEFAULT_TIME_ATTACK_BONUS_SECONDS: usize = 2;
+pub const MAX_TIME_ATTACK_BONUS_SECONDS: usize = 30;
+pub const MAX_SECONDS_PER_FRUIT: usize = 60;
+
+/// The number of ticks within which the next fruit has to be eaten to keep a combo going.
+pub const COMBO_WINDOW_TICKS: usize = 15;
+pub const DEFAULT_MAX_COMBO: usize = 5;
+pub const MAX_COMBO: usize = 10;
+
+pub const TITLE_TEXT_SIZE: f32 = 48.0;
+pub const BODY_TEXT_SIZE: f32 = 24.0;
+pub const SCORE_TEXT_SIZE: f32 = 20.0;
+pub const COUNTDOWN_TEXT_SIZE: f32 = 96.0;
+pub const LINE_HEIGHT: f32 = 1.5;
+
+/// The number of turns that can be queued up ahead of the snake's movement.
+pub const MAX_QUEUED_DIRECTIONS: usize = 2;
+/// How far the left stick of a gamepad has to be pushed to steer the snake, so that a stick
+/// drifting around its centre does not turn the snake by itself.
+pub const STICK_DEADZONE: f32 = 0.5;
+
+/// The shortest the time between two movements can get, however many fruits are eaten.
+pub const MIN_MILLISECONDS_PER_FRAME: usize = 80;
+pub const MAX_MILLISECONDS_PER_FRAME: usize = 1000;
+pub const MAX_SPEED_RAMP: usize = 50;
+/// The largest number of steps the snakes take in a single frame to make up for lost time.
+pub const MAX_CATCH_UP_TICKS: usize = 3;
+/// How much longer the time between two movements is while slowed down, in percent.
+pub const SLOW_FRAME_PERCENTAGE: usize = 150;
+/// How long the time between two movements is while sped up, in percent.
+pub const BOOST_FRAME_PERCENTAGE: usize = 65;
+/// How long the time between two movements is while the boost key is held, in percent.
+pub const HELD_BOOST_FRAME_PERCENTAGE: usize = 50;
+pub const MAX_BOOST_TAIL_COST: usize = 20;
+
+/// The number of seconds counted down before the snake starts moving.
+pub const COUNTDOWN_SECONDS: u8 = 3;
+pub const MILLISECONDS_PER_COUNT: usize = 1000;
+/// The time "Go!" stays on the screen for once the countdown is over.
+pub const GO_MILLISECONDS: usize = 600;
+
+/// The number of frames the snakes that crashed blink for before the game ends.
+pub const DYING_FRAMES: u8 = 6;
+pub const MILLISECONDS_PER_DYING_FRAME: usize = 120;
@@ -0,0 +1,129 @@
+use crate::config::GameConfig;
+use crate::structs::{Difficulty, GameEvent};
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// The version of the layout of the lines of the event log, which goes up whenever an event
+/// is added, taken away or written down differently.
+const VERSION: usize = 2;
+
+/// Represents a log of everything that happens over the course of the games played, which is
+/// kept in memory and only written out to its file once a game has ended, so that recording
+/// an event costs next to nothing.
+///
+/// Every line of the file is a JSON object. Every game starts with a line describing it,
+/// followed by a line for every event, numbered by the tick it happened on.
+#[derive(Clone, Debug)]
+pub struct EventLog {
+    /// The path of the file the log is written to.
+    path: PathBuf,
+
+    /// The lines recorded since the log was last written out.
+    lines: Vec<Line>,
+
+    /// Whether anything has been written to the file yet, until which point whatever was in
+    /// it before is yet to be replaced.
+    is_started: bool,
+}
+
+/// Represents a line of the event log.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Line {
+    /// The start of a game.
+    Start(GameStart),
+
+    /// Something that happened in the game last started.
+    Event(LoggedEvent),
+}
+
+/// Represents the line a game starts with, which describes how it was set up.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct GameStart {
+    pub(crate) version: usize,
+    pub(crate) seed: u64,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) difficulty: Difficulty,
+    pub(crate) players: usize,
+}
+
+/// Represents an event, numbered by the tick it happened on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct LoggedEvent {
+    pub(crate) tick: usize,
+
+    #[serde(flatten)]
+    pub(crate) event: GameEvent,
+}
+
+impl EventLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lines: Vec::new(),
+            is_started: false,
+        }
+    }
+
+    /// Starts off the events of a new game, set up according to `config`.
+    pub fn start_game(&mut self, config: &GameConfig) {
+        let dimensions = config.dimensions();
+
+        self.lines.push(Line::Start(GameStart {
+            version: VERSION,
+            seed: config.seed.unwrap_or_default(),
+            width: dimensions.x,
+            height: dimensions.y,
+            difficulty: config.difficulty,
+            players: config.player_count,
+        }));
+    }
+
+    /// Drops the events of the game being played, which is being replaced before it has
+    /// started.
+    pub fn discard_game(&mut self) {
+        let start = self
+            .lines
+            .iter()
+            .rposition(|line| matches!(line, Line::Start(_)));
+
+        if let Some(start) = start {
+            self.lines.truncate(start);
+        }
+    }
+
+    /// Records `event`, which happened on the given tick.
+    pub fn record(&mut self, tick: usize, event: &GameEvent) {
+        self.lines.push(Line::Event(LoggedEvent {
+            tick,
+            event: *event,
+        }));
+    }
+
+    /// Writes the events recorded since the last time out to the end of the file, replacing
+    /// the file the first time around.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.lines.is_empty() {
+            return Ok(());
+        }
+
+        let file = if self.is_started {
+            OpenOptions::new().append(true).open(&self.path)?
+        } else {
+            File::create(&self.path)?
+        };
+        self.is_started = true;
+
+        let mut file = BufWriter::new(file);
+        for line in self.lines.drain(..) {
+            serde_json::to_writer(&mut file, &line)?;
+            writeln!(file)?;
+        }
+
+        file.flush()
+    }
+}
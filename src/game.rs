@@ -1,304 +1,4490 @@
-use crate::{constants::*, structs::*};
+use crate::audio::{Sound, Sounds};
+use crate::event_log::EventLog;
+use crate::levels::{self, Level};
+use crate::preferences::{self, Preferences};
+use crate::replay::{self, Replay};
+use crate::save::{self, SavedGame};
+use crate::scoreboard::{self, Scoreboard};
+use crate::stats::{self, Record};
+use crate::theme::Theme;
+use crate::{bot, config::GameConfig, constants::*, highscore, structs::*};
 
-use std::collections::VecDeque;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use ggez::event::{self, KeyCode};
-use ggez::graphics::{self, Color, DrawParam, Mesh, Rect};
+use ggez::conf::FullscreenType;
+use ggez::event::{self, Axis, Button, GamepadId, KeyCode};
+use ggez::graphics::{
+    self, Color, DrawParam, Mesh, MeshBuilder, PxScale, Rect, Text, TextFragment,
+};
 use ggez::timer::delta;
-use ggez::{Context, GameResult};
+use ggez::{Context, GameError, GameResult};
 use rand::prelude::*;
+use rand::rngs::StdRng;
 
 /// Used for keeping track of the game's state.
 pub struct GameState {
-    /// The position of the snake's head.
-    head_position: Vector,
+    /// The snakes on the grid, one for each player.
+    snakes: Vec<Snake>,
 
-    /// The positions of the snake's tail parts.
-    tail_positions: VecDeque<Vector>,
+    /// The index of the player who won the game, if there are several players.
+    winner: Option<usize>,
 
-    /// The dimensions of the grid.
-    dimensions: Vector,
+    /// The indices of the players whose snakes have crashed.
+    crashed: Vec<usize>,
+
+    /// Whether the first player's snake is steered by the bot.
+    autopilot: bool,
+
+    /// Whether the snakes pass through walls and tails instead of crashing into them, and
+    /// stop at the edge of the grid instead of crashing into it.
+    ghost_mode: bool,
+
+    /// Whether ghost mode has been turned on at any point in the game, which keeps the game
+    /// off the high scores and out of the saves and replays.
+    used_ghost_mode: bool,
+
+    /// The gamepads in the order they were first used in, each steering the snake of the
+    /// player at the same index.
+    gamepads: Vec<Gamepad>,
+
+    /// The settings the game has been set up with.
+    config: GameConfig,
 
     /// The grid tiles.
     tiles: Vec<Vec<Tile>>,
 
-    /// The position of the fruit.
-    fruit_position: Vector,
+    /// The positions of the walls.
+    walls: Vec<Vector>,
+
+    /// The positions of the two ends of every portal.
+    portals: Vec<(Vector, Vector)>,
+
+    /// The number of rings around the edge of the grid that have been closed off with walls
+    /// as the arena shrinks.
+    rings_closed: usize,
+
+    /// The level the game has been laid out from, if any.
+    level: Option<Level>,
+
+    /// The fruits on the grid.
+    fruits: Vec<Fruit>,
+
+    /// The position of the bonus fruit, if there is one, and the number of ticks left
+    /// before it disappears.
+    bonus_fruit: Option<(Vector, usize)>,
+
+    /// The number of ticks left before the next bonus fruit appears.
+    bonus_fruit_cooldown: usize,
+
+    /// The positions of the poisoned tiles, each with the number of ticks left before it
+    /// clears up.
+    hazards: Vec<(Vector, usize)>,
+
+    /// The number of ticks left before the next tile is poisoned.
+    hazard_cooldown: usize,
+
+    /// The source of randomness for the layout of the grid.
+    rng: StdRng,
+
+    /// The direction each snake has moved in, tick by tick.
+    moves: Vec<Vec<Direction>>,
+
+    /// Whether the snakes were boosted, tick by tick, so that a replay pays for the boost
+    /// the same way.
+    boosts: Vec<bool>,
 
-    /// The direction of movement of the snake.
-    movement_direction: Direction,
+    /// The recorded game being played back, if any.
+    replay: Option<Replay>,
 
-    /// The next direction of movement of the snake.
-    queued_direction: Option<Direction>,
+    /// Whether the game has been saved as a replay since it ended.
+    replay_saved: bool,
+
+    /// Whether the game has been resumed from a save, in which case the moves made before
+    /// it was saved are missing.
+    resumed: bool,
 
     /// The current state of the game.
     state: State,
 
+    /// Whether the game has been paused, which stops the clock and the snakes until it is
+    /// carried on with.
+    paused: bool,
+
+    /// The best score achieved across all games played on the same difficulty.
+    high_score: usize,
+
+    /// The path of the file the high score is stored in.
+    high_score_path: PathBuf,
+
+    /// The best scores achieved across all single-player games.
+    scoreboard: Scoreboard,
+
+    /// The initials being typed in for a score that has made it onto the scoreboard, if any.
+    name_entry: Option<String>,
+
+    /// Whether the scoreboard is shown in place of the outcome of the game.
+    showing_scoreboard: bool,
+
+    /// The number of fruits eaten by all the snakes together.
+    fruits_eaten: usize,
+
+    /// The number of ticks the game has been running for.
+    ticks: usize,
+
+    /// The effect of the last power-up eaten, if it still lasts, and the number of ticks it
+    /// lasts for.
+    active_power_up: Option<(PowerUp, usize)>,
+
     /// The time elapsed since the last update.
     ms_since_last_update: usize,
+
+    /// Whether the boost key is held, speeding the snakes up.
+    boost_active: bool,
+
+    /// The number of tiles the snakes have moved while boosted.
+    boosted_tiles: usize,
+
+    /// The part of a millisecond left over from the time between the last two frames, which
+    /// is carried over to the next frame so that the clock does not fall behind at high
+    /// frame rates.
+    delta_remainder: Duration,
+
+    /// The log the events of the games played are recorded in, if they are recorded.
+    event_log: Option<EventLog>,
+
+    /// The time the game has been running for, not counting the countdown.
+    elapsed_ms: usize,
+
+    /// The time the game had been running for when a fruit was last eaten, which the time
+    /// given to eat the next one in a timed game counts from.
+    last_fruit_ms: usize,
+
+    /// The size of the area the game is drawn in.
+    screen_size: (f32, f32),
+
+    /// Whether the game is shown in fullscreen.
+    fullscreen: bool,
+
+    /// Whether only the tiles near the heads of the snakes can be seen while the game is
+    /// going on.
+    fog_of_war: bool,
+
+    /// Whether the grid is drawn as an overview, in which every tile is a flat block of
+    /// colour without a border, grid lines, eyes or fading tails, so that large grids are
+    /// easier to take in at a glance.
+    overview: bool,
+
+    /// The sounds played as the game goes on.
+    sounds: Sounds,
+
+    /// The checkerboard the grid is drawn on, together with the layout it was built for.
+    background: Option<(Layout, Mesh)>,
 }
 
 impl GameState {
-    pub fn new(dimensions: Vector) -> GameResult<Self> {
-        let mut rng = thread_rng();
+    pub fn new(config: GameConfig) -> GameResult<Self> {
+        let dimensions = config.dimensions();
 
-        let head_initial_position = Vector {
-            x: dimensions.x / 2,
-            y: dimensions.y / 2,
+        let snakes = if config.player_count > 1 {
+            // The players start in opposite corners, heading towards each other.
+            vec![
+                Snake::new(
+                    Vector {
+                        x: dimensions.x / 4,
+                        y: dimensions.y / 4,
+                    },
+                    Direction::Right,
+                ),
+                Snake::new(
+                    Vector {
+                        x: dimensions.x - 1 - dimensions.x / 4,
+                        y: dimensions.y - 1 - dimensions.y / 4,
+                    },
+                    Direction::Left,
+                ),
+            ]
+        } else {
+            vec![Snake::new(
+                Vector {
+                    x: dimensions.x / 2,
+                    y: dimensions.y / 2,
+                },
+                Direction::Right,
+            )]
         };
 
-        let mut column: Vec<Vec<Tile>> = Vec::with_capacity(dimensions.y);
-        for y in 0..dimensions.y {
-            let mut row = Vec::with_capacity(dimensions.x);
+        let mut state = Self::with_grid(config, snakes);
 
-            for x in 0..dimensions.x {
-                row.push(Tile {
-                    position: Vector { x, y },
-                    is_occupied: false,
-                })
+        state.grow_initial_tails();
+        state.place_walls();
+        state.place_portals();
+        state.place_fruits(config.fruit_count());
+
+        Ok(state)
+    }
+
+    /// Creates a game with the default settings on a grid of the given dimensions, laid out
+    /// from `seed`, so that the same seed always places the fruits and walls the same way.
+    pub fn new_with_seed(dimensions: Vector, seed: u64) -> GameResult<Self> {
+        Self::new(GameConfig {
+            width: Some(dimensions.x),
+            height: Some(dimensions.y),
+            seed: Some(seed),
+            ..GameConfig::default()
+        })
+    }
+
+    /// Creates a single-player game laid out according to a level map.
+    ///
+    /// The level takes the place of the grid size and walls given in `config`.
+    pub fn from_level(level: Level, config: GameConfig) -> GameResult<Self> {
+        let config = GameConfig {
+            width: Some(level.dimensions.x),
+            height: Some(level.dimensions.y),
+            obstacle_count: 0,
+            portal_count: 0,
+            player_count: 1,
+            ..config
+        };
+
+        let snakes = vec![Snake::new(level.start_position, Direction::Right)];
+
+        let mut state = Self::with_grid(config, snakes);
+
+        for wall in &level.walls {
+            state.tiles[wall.y][wall.x].kind = TileKind::Obstacle;
+            state.walls.push(*wall);
+        }
+
+        for (entrance, exit) in &level.portals {
+            state.tiles[entrance.y][entrance.x].kind = TileKind::Portal;
+            state.tiles[exit.y][exit.x].kind = TileKind::Portal;
+        }
+        state.portals = level.portals.clone();
+
+        for position in &level.fruit_positions {
+            state.tiles[position.y][position.x].kind = TileKind::Fruit;
+            state.fruits.push(Fruit {
+                position: *position,
+                kind: FruitKind::Normal,
+            });
+        }
+
+        state.grow_initial_tails();
+        state.place_fruits(config.fruit_count().saturating_sub(state.fruits.len()));
+        state.level = Some(level);
+
+        Ok(state)
+    }
+
+    /// Creates a single-player game laid out according to the map file at `path`.
+    pub fn from_level_file(path: &Path, config: GameConfig) -> GameResult<Self> {
+        let level = levels::load(path).map_err(GameError::ResourceLoadError)?;
+
+        Self::from_level(level, config)
+    }
+
+    /// Creates a game that plays back a recorded one instead of listening to the players.
+    pub fn from_replay(replay: Replay) -> GameResult<Self> {
+        let mut state = Self::new(replay.config)?;
+        state.replay = Some(replay);
+
+        // The difficulty was chosen when the game was recorded, so a replay skips the menu.
+        state.start_countdown();
+
+        Ok(state)
+    }
+
+    /// Rebuilds a game in progress from a save, checking that everything saved fits on the
+    /// grid without overlapping rather than trusting the save blindly.
+    pub fn from_save(saved: SavedGame) -> Result<Self, String> {
+        if !matches!(saved.state, State::Running | State::Countdown(_)) {
+            return Err("Only games in progress can be resumed.".to_owned());
+        }
+
+        // The walls of the level are saved apart from the walls of the arena, but are on the
+        // grid all the same.
+        if let Some(level) = &saved.level {
+            if level.walls.iter().any(|wall| !saved.walls.contains(wall)) {
+                return Err("The save is missing some of the walls of its level.".to_owned());
             }
+        }
 
-            column.push(row);
+        let mut state = Self::with_grid(saved.config, Vec::new());
+
+        for wall in &saved.walls {
+            state.claim_tile(wall, TileKind::Obstacle)?;
+        }
+
+        for (entrance, exit) in &saved.portals {
+            state.claim_tile(entrance, TileKind::Portal)?;
+            state.claim_tile(exit, TileKind::Portal)?;
         }
 
-        Ok(Self {
-            head_position: head_initial_position,
-            tail_positions: VecDeque::new(),
-            dimensions: dimensions.clone(),
-            fruit_position: {
-                loop {
-                    let position = Vector {
-                        x: rng.gen_range(0..dimensions.x),
-                        y: rng.gen_range(0..dimensions.y),
-                    };
+        let fruit_positions = saved.fruits.iter().map(|fruit| &fruit.position);
+        let bonus_fruit_position = saved.bonus_fruit.as_ref().map(|(position, _)| position);
 
-                    if position == head_initial_position {
-                        continue;
-                    }
+        for position in fruit_positions.chain(bonus_fruit_position) {
+            state.claim_tile(position, TileKind::Fruit)?;
+        }
 
-                    column[position.x][position.y].is_occupied = true;
+        for (position, _) in &saved.hazards {
+            state.claim_tile(position, TileKind::Poison)?;
+        }
 
-                    break position;
-                }
-            },
-            tiles: column,
-            movement_direction: Direction::Right,
-            queued_direction: None,
-            state: State::Running,
-            ms_since_last_update: MILLISECONDS_PER_FRAME,
-        })
+        for snake in &saved.snakes {
+            for position in std::iter::once(&snake.head_position).chain(&snake.tail_positions) {
+                state.claim_tile(position, TileKind::Occupied)?;
+            }
+        }
+
+        state.snakes = saved.snakes;
+        state.autopilot = saved.autopilot;
+        state.walls = saved.walls;
+        state.portals = saved.portals;
+        state.rings_closed = saved.rings_closed;
+        state.level = saved.level;
+        state.fruits = saved.fruits;
+        state.bonus_fruit = saved.bonus_fruit;
+        state.bonus_fruit_cooldown = saved.bonus_fruit_cooldown;
+        state.hazards = saved.hazards;
+        state.hazard_cooldown = saved.hazard_cooldown;
+        state.rng = StdRng::seed_from_u64(saved.rng_seed);
+        state.resumed = true;
+        state.state = saved.state;
+        state.fruits_eaten = saved.fruits_eaten;
+        state.ticks = saved.ticks;
+        state.boosted_tiles = saved.boosted_tiles;
+        state.active_power_up = saved.active_power_up;
+        state.ms_since_last_update = saved.ms_since_last_update;
+        state.elapsed_ms = saved.elapsed_ms;
+        state.last_fruit_ms = saved.last_fruit_ms;
+
+        Ok(state)
     }
 
-    fn place_fruit(&mut self) -> Option<&Vector> {
-        let mut unoccupied_tiles: Vec<&mut Tile> = self
+    /// Marks the empty tile at `position` as taken up by `kind`.
+    ///
+    /// A snake may also lie across the end of a portal, which it has passed through.
+    fn claim_tile(&mut self, position: &Vector, kind: TileKind) -> Result<(), String> {
+        let tile = self
             .tiles
-            .iter_mut()
-            .map(|tiles| {
-                tiles
-                    .iter_mut()
-                    .filter(|tile| !tile.is_occupied)
-                    .collect::<Vec<&mut Tile>>()
-            })
-            .flatten()
-            .collect();
+            .get_mut(position.y)
+            .and_then(|row| row.get_mut(position.x))
+            .ok_or_else(|| format!("{},{} lies outside the grid.", position.x, position.y))?;
 
-        if unoccupied_tiles.is_empty() {
-            return None;
+        let passes_through_portal = tile.kind == TileKind::Portal && kind == TileKind::Occupied;
+
+        if tile.kind != TileKind::Empty && !passes_through_portal {
+            return Err(format!(
+                "{},{} is taken up by more than one thing.",
+                position.x, position.y
+            ));
         }
 
-        let mut rng = thread_rng();
+        tile.kind = kind;
 
-        let random_index = rng.gen_range(0..unoccupied_tiles.len());
-        let mut tile = unoccupied_tiles.swap_remove(random_index);
+        Ok(())
+    }
 
-        self.fruit_position = tile.position.clone();
-        tile.is_occupied = true;
+    /// Writes the game in progress to the file at `path`, to be picked up again with
+    /// [`GameState::load`].
+    pub fn save(&mut self, path: &Path) -> std::io::Result<()> {
+        let saved = self.snapshot();
 
-        Some(&tile.position)
+        save::save(path, &saved)
     }
-}
 
-impl event::EventHandler for GameState {
-    fn key_down_event(
-        &mut self,
-        _: &mut Context,
-        keycode: event::KeyCode,
-        _: event::KeyMods,
-        _: bool,
-    ) {
-        match keycode {
-            KeyCode::Up => {
-                if self.movement_direction == Direction::Down {
-                    return;
-                }
+    /// Gathers everything needed to carry on playing the game in progress.
+    fn snapshot(&mut self) -> SavedGame {
+        // The randomness is picked up again from a fresh seed both here and in the resumed
+        // game, so that both carry on the same way.
+        let rng_seed = self.rng.gen();
+        self.rng = StdRng::seed_from_u64(rng_seed);
 
-                self.queued_direction = Some(Direction::Up);
-            }
-            KeyCode::Down => {
-                if self.movement_direction == Direction::Up {
-                    return;
-                }
+        SavedGame {
+            config: self.config,
+            state: self.state,
+            ms_since_last_update: self.ms_since_last_update,
+            elapsed_ms: self.elapsed_ms,
+            last_fruit_ms: self.last_fruit_ms,
+            active_power_up: self.active_power_up,
+            fruits_eaten: self.fruits_eaten,
+            ticks: self.ticks,
+            boosted_tiles: self.boosted_tiles,
+            bonus_fruit: self.bonus_fruit,
+            bonus_fruit_cooldown: self.bonus_fruit_cooldown,
+            hazards: self.hazards.clone(),
+            hazard_cooldown: self.hazard_cooldown,
+            rng_seed,
+            autopilot: self.autopilot,
+            walls: self.walls.clone(),
+            rings_closed: self.rings_closed,
+            portals: self.portals.clone(),
+            fruits: self.fruits.clone(),
+            level: self.level.clone(),
+            snakes: self.snakes.clone(),
+        }
+    }
 
-                self.queued_direction = Some(Direction::Down);
-            }
-            KeyCode::Left => {
-                if self.movement_direction == Direction::Right {
-                    return;
+    /// Reads the game saved to the file at `path`, rebuilding the occupancy of the grid from
+    /// the positions of everything on it.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        save::load(path).and_then(Self::from_save)
+    }
+
+    /// Writes the game in progress to the save file.
+    fn save_game(&mut self) {
+        // Snakes in ghost mode may lie across walls and each other, which a save cannot hold.
+        if self.used_ghost_mode {
+            eprintln!("Games played in ghost mode cannot be saved.");
+            return;
+        }
+
+        let path = save::default_path();
+
+        match self.save(&path) {
+            Ok(()) => println!("Saved the game to '{}'.", path.display()),
+            Err(error) => eprintln!("Failed to save the game: {}", error),
+        }
+    }
+
+    /// Replaces the current game with the playback of the replay at `path`, which is seeded
+    /// and steered from the recording alone, leaving the players no say in it.
+    pub fn play_replay(&mut self, path: &Path) -> Result<(), String> {
+        let mut replay = replay::load(path)?;
+        replay.config = replay.config.with_unrecorded_settings(&self.config);
+
+        let state = Self::from_replay(replay).map_err(|error| error.to_string())?;
+
+        *self = Self {
+            gamepads: std::mem::take(&mut self.gamepads),
+            screen_size: self.screen_size,
+            fullscreen: self.fullscreen,
+            overview: self.overview,
+            fog_of_war: self.fog_of_war,
+            sounds: std::mem::take(&mut self.sounds),
+            ..state
+        };
+
+        Ok(())
+    }
+
+    /// Replaces the current game with the one in the save file.
+    fn load_game(&mut self) {
+        match Self::load(&save::default_path()) {
+            Ok(mut state) => {
+                state.config = state.config.with_unrecorded_settings(&self.config);
+
+                *self = Self {
+                    gamepads: std::mem::take(&mut self.gamepads),
+                    screen_size: self.screen_size,
+                    fullscreen: self.fullscreen,
+                    overview: self.overview,
+                    fog_of_war: self.fog_of_war,
+                    sounds: std::mem::take(&mut self.sounds),
+                    ..state
                 }
+            }
+            Err(message) => eprintln!("Failed to load the game: {}", message),
+        }
+    }
 
-                self.queued_direction = Some(Direction::Left);
+    /// Creates a game with an empty grid apart from the heads of the snakes.
+    fn with_grid(mut config: GameConfig, snakes: Vec<Snake>) -> Self {
+        let dimensions = config.dimensions();
+
+        // The seed is kept even when picked at random, so that the game can be replayed.
+        let seed = *config.seed.get_or_insert_with(random);
+
+        let mut column: Vec<Vec<Tile>> = Vec::with_capacity(dimensions.y);
+        for y in 0..dimensions.y {
+            let mut row = Vec::with_capacity(dimensions.x);
+
+            for x in 0..dimensions.x {
+                row.push(Tile {
+                    position: Vector { x, y },
+                    kind: if snakes
+                        .iter()
+                        .any(|snake| snake.head_position == Vector { x, y })
+                    {
+                        TileKind::Occupied
+                    } else {
+                        TileKind::Empty
+                    },
+                })
             }
-            KeyCode::Right => {
-                if self.movement_direction == Direction::Left {
-                    return;
-                }
 
-                self.queued_direction = Some(Direction::Right);
+            column.push(row);
+        }
+
+        let high_score_path = highscore::default_path();
+
+        Self {
+            snakes,
+            winner: None,
+            crashed: Vec::new(),
+            autopilot: false,
+            ghost_mode: false,
+            used_ghost_mode: false,
+            gamepads: Vec::new(),
+            config,
+            fruits: Vec::with_capacity(config.fruit_count()),
+            bonus_fruit: None,
+            bonus_fruit_cooldown: BONUS_FRUIT_COOLDOWN,
+            hazards: Vec::new(),
+            hazard_cooldown: config.poison_interval,
+            tiles: column,
+            walls: Vec::with_capacity(config.obstacle_count),
+            portals: Vec::new(),
+            rings_closed: 0,
+            level: None,
+            rng: StdRng::seed_from_u64(seed),
+            moves: Vec::new(),
+            boosts: Vec::new(),
+            replay: None,
+            replay_saved: false,
+            resumed: false,
+            state: State::Menu,
+            paused: false,
+            high_score: highscore::load(&high_score_path, config.difficulty),
+            high_score_path,
+            scoreboard: scoreboard::load(&scoreboard::default_path()),
+            name_entry: None,
+            showing_scoreboard: false,
+            fruits_eaten: 0,
+            ticks: 0,
+            active_power_up: None,
+            ms_since_last_update: 0,
+            boost_active: false,
+            boosted_tiles: 0,
+            delta_remainder: Duration::ZERO,
+            event_log: None,
+            elapsed_ms: 0,
+            last_fruit_ms: 0,
+            screen_size: window_size(&config),
+            fullscreen: false,
+            overview: false,
+            fog_of_war: false,
+            sounds: Sounds::default(),
+            background: None,
+        }
+    }
+
+    /// Lays out the tails the snakes start out with behind their heads, cutting them short
+    /// where they would run into the edge of the grid or anything else on it.
+    fn grow_initial_tails(&mut self) {
+        for snake in &mut self.snakes {
+            let behind = snake.movement_direction.opposite();
+            let mut position = snake.head_position;
+
+            for _ in 0..self.config.initial_tail_length {
+                position = match position.neighbour(behind, &self.config.dimensions()) {
+                    Some(next) if self.tiles[next.y][next.x].kind == TileKind::Empty => next,
+                    _ => break,
+                };
+
+                self.tiles[position.y][position.x].kind = TileKind::Occupied;
+                snake.push_tail(position);
             }
-            _ => (),
         }
     }
 
-    fn update(&mut self, context: &mut Context) -> GameResult {
-        if self.state == State::Lost {
-            return Ok(());
+    /// Lets the bot steer the first player's snake, or hands control back to the player.
+    pub fn set_autopilot(&mut self, enabled: bool) {
+        self.autopilot = enabled;
+
+        // Turns queued by the player before the bot took over should not be carried out.
+        if let Some(snake) = self.snakes.first_mut() {
+            snake.queued_directions.clear();
         }
+    }
 
-        self.ms_since_last_update += delta(context).as_millis() as usize;
-        if self.ms_since_last_update < MILLISECONDS_PER_FRAME {
-            return Ok(());
+    /// Leaves the start menu, counting down to the start of the game.
+    fn start_countdown(&mut self) {
+        if self.state == State::Menu {
+            self.state = State::Countdown(COUNTDOWN_SECONDS);
         }
-        self.ms_since_last_update -= MILLISECONDS_PER_FRAME;
+    }
+
+    /// Switches to the next easier difficulty when going left in the menu, or to the next
+    /// harder one when going right, along with the high score kept for it.
+    ///
+    /// The difficulty decides the size of the grid and the number of fruits on it unless they
+    /// were chosen, so the board is set up again from the same seed.
+    fn choose_difficulty(&mut self, direction: Direction) {
+        let index = Difficulty::ALL
+            .iter()
+            .position(|difficulty| *difficulty == self.config.difficulty)
+            .unwrap_or_default();
+
+        let index = match direction {
+            Direction::Left => index.saturating_sub(1),
+            Direction::Right => (index + 1).min(Difficulty::ALL.len() - 1),
+            Direction::Up | Direction::Down => return,
+        };
 
-        let previous_position = self.head_position.clone();
+        let config = GameConfig {
+            difficulty: Difficulty::ALL[index],
+            ..self.config
+        };
+
+        let state = match &self.level {
+            Some(level) => Self::from_level(level.clone(), config),
+            None => Self::new(config),
+        };
 
-        match self.queued_direction {
-            Some(direction) => {
-                self.movement_direction = direction.clone();
-                self.queued_direction = None
+        let mut state = match state {
+            Ok(state) => state,
+            Err(error) => {
+                eprintln!("Failed to set up the board: {}", error);
+                return;
             }
-            None => (),
+        };
+
+        state.autopilot = self.autopilot;
+        state.gamepads = std::mem::take(&mut self.gamepads);
+
+        if let Some(event_log) = &mut self.event_log {
+            event_log.discard_game();
         }
+        self.carry_over_to(state);
+    }
 
-        match self.movement_direction {
-            Direction::Up => {
-                if self.head_position.y == 0 {
-                    self.state = State::Lost;
-                    return Ok(());
-                }
+    /// Hands the first player's snake over to the bot, or takes it back from the bot.
+    pub fn toggle_autopilot(&mut self) {
+        self.set_autopilot(!self.autopilot);
+    }
 
-                self.head_position.y -= 1;
-            }
-            Direction::Down => {
-                if self.head_position.y + 1 == self.dimensions.y {
-                    self.state = State::Lost;
-                    return Ok(());
-                }
+    /// Lets the snakes pass through walls and tails without crashing, or makes them crash
+    /// into them again from the next tick on.
+    pub fn toggle_ghost_mode(&mut self) {
+        self.ghost_mode = !self.ghost_mode;
+        self.used_ghost_mode |= self.ghost_mode;
+    }
 
-                self.head_position.y += 1;
-            }
-            Direction::Right => {
-                if self.head_position.x + 1 == self.dimensions.x {
-                    self.state = State::Lost;
-                    return Ok(());
-                }
+    /// Restarts the game on a fresh board of the same dimensions, or the same level, or
+    /// plays the replay back from the start again.
+    pub fn restart(&mut self) -> GameResult {
+        let mut state = match &self.replay {
+            Some(replay) => Self::from_replay(replay.clone())?,
+            None => {
+                // The next board is seeded from this one, so a whole session can be replayed
+                // from the seed it started with.
+                let config = GameConfig {
+                    seed: Some(self.rng.gen()),
+                    ..self.config
+                };
 
-                self.head_position.x += 1;
-            }
-            Direction::Left => {
-                if self.head_position.x == 0 {
-                    self.state = State::Lost;
-                    return Ok(());
-                }
+                let mut state = match &self.level {
+                    Some(level) => Self::from_level(level.clone(), config)?,
+                    None => Self::new(config)?,
+                };
 
-                self.head_position.x -= 1;
+                state.autopilot = self.autopilot;
+                state.gamepads = std::mem::take(&mut self.gamepads);
+                state
             }
+        };
+
+        // The difficulty has already been chosen, so the next game skips the menu.
+        state.start_countdown();
+        self.carry_over_to(state);
+
+        Ok(())
+    }
+
+    /// Replaces this game with `state`, carrying over the window, the sounds and the event
+    /// log, which outlast any one game.
+    fn carry_over_to(&mut self, mut state: Self) {
+        state.screen_size = self.screen_size;
+        state.fullscreen = self.fullscreen;
+        state.overview = self.overview;
+        state.fog_of_war = self.fog_of_war;
+        state.config = state.config.with_unrecorded_settings(&self.config);
+        state.sounds = std::mem::take(&mut self.sounds);
+        state.background = self.background.take();
+        if let Some(event_log) = self.event_log.take() {
+            state.start_event_log(event_log);
         }
+        *self = state;
+    }
+
+    /// The size of the window required to fit the grid, its border and the HUD.
+    pub fn window_size(&self) -> (f32, f32) {
+        window_size(&self.config)
+    }
+
+    /// Loads the sounds played as the game goes on, at the volume set up in the config.
+    pub fn load_sounds(&mut self, context: &mut Context) {
+        self.sounds = Sounds::load(context, self.config.volume);
+    }
+
+    /// Silences the sounds, or lets them be heard again.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.sounds.muted = muted;
+    }
 
-        self.tiles[self.head_position.y][self.head_position.x].is_occupied = true;
+    /// Switches the window in or out of fullscreen.
+    pub fn set_fullscreen(&mut self, context: &mut Context, enabled: bool) -> GameResult {
+        let fullscreen_type = if enabled {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        graphics::set_fullscreen(context, fullscreen_type)?;
 
-        if self.tail_positions.contains(&self.head_position) {
-            self.state = State::Lost;
+        // The window does not always remember its size while in fullscreen, so it is put
+        // back to the size the grid was opened at. The board is rescaled either way by the
+        // resize event that follows.
+        if !enabled {
+            let (width, height) = self.window_size();
+            graphics::set_drawable_size(context, width, height)?;
         }
 
-        self.tail_positions.push_front(previous_position);
+        self.fullscreen = enabled;
 
-        if self.head_position == self.fruit_position {
-            // No position means a fruit could not be placed, which in turn means that
-            // there are no more unoccupied tiles.
-            match self.place_fruit() {
-                Some(position) => self.fruit_position = position.clone(),
-                None => self.state = State::Won,
-            }
+        Ok(())
+    }
 
-            return Ok(());
+    /// Switches the window in or out of fullscreen, remembering the choice for the next
+    /// time the game is started.
+    fn toggle_fullscreen(&mut self, context: &mut Context) {
+        if let Err(error) = self.set_fullscreen(context, !self.fullscreen) {
+            eprintln!("Failed to switch fullscreen: {}", error);
+            return;
         }
 
-        if let Some(tail_position) = self.tail_positions.pop_back() {
-            self.tiles[tail_position.x][tail_position.y].is_occupied = false;
+        let preferences = Preferences {
+            fullscreen: self.fullscreen,
+        };
+        if let Err(error) = preferences::save(&preferences::default_path(), &preferences) {
+            eprintln!("Failed to save the preferences: {}", error);
         }
+    }
 
-        Ok(())
+    /// Hides the tiles away from the heads of the snakes while the game is going on, or
+    /// shows the whole grid again.
+    pub fn set_fog_of_war(&mut self, enabled: bool) {
+        self.fog_of_war = enabled;
     }
 
-    fn draw(&mut self, context: &mut Context) -> GameResult {
-        graphics::clear(context, BACKGROUND_COLOR);
+    /// Records everything that happens over the course of this game and the ones after it,
+    /// writing it out to the file at `path` whenever a game ends and when the window is
+    /// closed.
+    pub fn set_event_log(&mut self, path: PathBuf) {
+        self.start_event_log(EventLog::new(path));
+    }
 
-        for i in 0..self.dimensions.x {
-            for j in 0..self.dimensions.y {
-                let is_even = (j * 10 + i) % 2 == (j % 2);
+    /// Starts recording the events of this game in `event_log`, beginning with the fruits
+    /// the grid starts out with.
+    fn start_event_log(&mut self, mut event_log: EventLog) {
+        event_log.start_game(&self.config);
 
-                draw_tile(
-                    context,
-                    &Vector { x: i, y: j },
-                    if is_even {
-                        TILE_COLORS.0
-                    } else {
-                        TILE_COLORS.1
-                    },
-                    1.0,
-                )?;
+        for position in self.fruit_positions() {
+            event_log.record(self.ticks, &GameEvent::FruitPlaced { position });
+        }
+
+        self.event_log = Some(event_log);
+    }
+
+    /// Records `events` in the event log, if there is one, writing the log out if the game
+    /// has ended.
+    fn log_events(&mut self, events: &[GameEvent]) {
+        let event_log = match &mut self.event_log {
+            Some(event_log) => event_log,
+            None => return,
+        };
+
+        for event in events {
+            event_log.record(self.ticks, event);
+        }
+
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Ended { .. }))
+        {
+            if let Err(error) = event_log.flush() {
+                eprintln!("Failed to save the event log: {}", error);
             }
         }
+    }
+
+    /// The positions of the fruits on the grid, the bonus fruit included.
+    fn fruit_positions(&self) -> Vec<Vector> {
+        self.fruits
+            .iter()
+            .map(|fruit| fruit.position)
+            .chain(self.bonus_fruit.map(|(position, _)| position))
+            .collect()
+    }
+
+    /// Switches between drawing the grid in full detail and as an overview of flat blocks.
+    pub fn toggle_overview(&mut self) {
+        self.overview = !self.overview;
+        // The checkerboard and the grid lines are left out of the overview.
+        self.background = None;
+    }
+
+    /// Fits the grid into the area below the HUD, with tiles as large as possible while
+    /// staying square, leaving the space left over as margins on either side.
+    fn layout(&self) -> Layout {
+        let (width, height) = self.screen_size;
+        let height = height - HUD_HEIGHT;
+        let dimensions = self.config.dimensions();
+        let columns = dimensions.x as f32;
+        let rows = dimensions.y as f32;
+
+        // The overview leaves out the border to make the most of the space.
+        let border_size = if self.overview {
+            0.0
+        } else {
+            self.config.border_size
+        };
+
+        let tile_size = ((width - 2.0 * border_size) / columns)
+            .min((height - 2.0 * border_size) / rows)
+            .max(0.0);
+
+        Layout {
+            tile_size,
+            origin: (
+                (width - columns * tile_size) / 2.0,
+                HUD_HEIGHT + (height - rows * tile_size) / 2.0,
+            ),
+        }
+    }
 
-        draw_tile(context, &self.head_position, HEAD_COLOR, 0.7)?;
-        draw_tile(context, &self.fruit_position, FRUIT_COLOR, 0.4)?;
+    /// Covers the tiles further than [`FOG_RADIUS`] from every head with the background
+    /// colour, thinning the cover out over the tiles just beyond the radius.
+    fn add_fog(&self, builder: &mut MeshBuilder, layout: &Layout) -> GameResult {
+        let Vector {
+            x: width,
+            y: height,
+        } = self.config.dimensions();
 
-        for (index, tail_piece) in self.tail_positions.iter().enumerate() {
-            let is_even = index % 2 == 0;
+        for y in 0..height {
+            for x in 0..width {
+                let position = Vector { x, y };
+                let distance = self
+                    .snakes
+                    .iter()
+                    .map(|snake| {
+                        snake.head_position.x.abs_diff(x) + snake.head_position.y.abs_diff(y)
+                    })
+                    .min()
+                    .unwrap_or_default();
 
-            draw_tile(
-                context,
-                tail_piece,
-                if is_even {
-                    TAIL_COLORS.0
+                let opacity = if distance <= FOG_RADIUS {
+                    continue;
+                } else if distance == FOG_RADIUS + 1 {
+                    FOG_EDGE_OPACITY
                 } else {
-                    TAIL_COLORS.1
-                },
-                0.5,
-            )?;
+                    1.0
+                };
+                let color = Color {
+                    a: opacity,
+                    ..self.config.theme.background
+                };
+
+                add_tile(builder, &position, color, 1.0, layout)?;
+            }
         }
 
-        graphics::present(context)?;
         Ok(())
     }
-}
 
-fn draw_tile(context: &mut Context, position: &Vector, color: Color, size: f32) -> GameResult {
-    let tile_size = size * TILE_SIZE;
-    let padding_size = TILE_SIZE - tile_size;
+    /// Darkens the board and shows the outcome of the game on top of it.
+    fn draw_overlay(&self, context: &mut Context) -> GameResult {
+        if self.paused {
+            let (width, height) = self.screen_size;
 
-    let tile = Mesh::new_rectangle(
-        context,
-        graphics::DrawMode::fill(),
-        Rect {
-            x: padding_size / 2.0 + BORDER_SIZE + (position.x as f32) * TILE_SIZE,
-            y: padding_size / 2.0 + BORDER_SIZE + (position.y as f32) * TILE_SIZE,
-            w: tile_size,
-            h: tile_size,
-        },
-        color,
-    )?;
+            self.draw_shade(context)?;
 
-    graphics::draw(context, &tile, DrawParam::default())
+            return draw_text(
+                context,
+                "Paused",
+                TITLE_TEXT_SIZE,
+                [width / 2.0, height / 2.0],
+            );
+        }
+
+        let title = match (self.state, self.winner) {
+            (State::Lost, _) => "Game Over".to_owned(),
+            (State::Won, Some(winner)) => format!("Player {} Wins!", winner + 1),
+            (State::Won, None) => "You Win!".to_owned(),
+            (State::Draw, _) => "Draw!".to_owned(),
+            (State::TimeUp, _) => "Time's Up!".to_owned(),
+            // The countdown ends on a "Go!" over the first moments of the game.
+            (State::Running, _) if self.elapsed_ms < GO_MILLISECONDS => {
+                let (width, height) = self.screen_size;
+
+                return draw_text(
+                    context,
+                    "Go!",
+                    COUNTDOWN_TEXT_SIZE,
+                    [width / 2.0, height / 2.0],
+                );
+            }
+            (State::Running | State::Dying(_), _) => return Ok(()),
+            (State::Menu, _) => {
+                self.draw_shade(context)?;
+
+                return self.draw_panel(context, &self.menu_lines());
+            }
+            (State::Countdown(count), _) => {
+                let (width, height) = self.screen_size;
+
+                return draw_text(
+                    context,
+                    &count.to_string(),
+                    COUNTDOWN_TEXT_SIZE,
+                    [width / 2.0, height / 2.0],
+                );
+            }
+        };
+
+        self.draw_shade(context)?;
+
+        if self.showing_scoreboard {
+            return self.draw_panel(context, &self.scoreboard_lines());
+        }
+
+        let mut lines = vec![(title, TITLE_TEXT_SIZE)];
+
+        match self.snakes.as_slice() {
+            [snake] => {
+                lines.push((format!("Score: {}", snake.score), BODY_TEXT_SIZE));
+                lines.push((format!("Base score: {}", snake.base_score), BODY_TEXT_SIZE));
+                lines.push((format!("Best combo: x{}", snake.best_combo), BODY_TEXT_SIZE));
+                lines.push((format!("Best: {}", self.high_score), BODY_TEXT_SIZE));
+                lines.push((
+                    format!(
+                        "Length: {}, fruits eaten: {}",
+                        snake.tail_positions.len() + 1,
+                        self.fruits_eaten
+                    ),
+                    BODY_TEXT_SIZE,
+                ));
+                lines.push((
+                    format!(
+                        "Time: {}, ticks: {}, turns: {}",
+                        format_duration(self.elapsed_ms),
+                        self.ticks,
+                        snake.turns
+                    ),
+                    BODY_TEXT_SIZE,
+                ));
+            }
+            snakes => {
+                for (index, snake) in snakes.iter().enumerate() {
+                    lines.push((
+                        format!(
+                            "Player {}: {} (base {}, best combo x{}, {} turns)",
+                            index + 1,
+                            snake.score,
+                            snake.base_score,
+                            snake.best_combo,
+                            snake.turns
+                        ),
+                        BODY_TEXT_SIZE,
+                    ));
+                }
+
+                lines.push((
+                    format!(
+                        "Time: {}, ticks: {}, fruits eaten: {}",
+                        format_duration(self.elapsed_ms),
+                        self.ticks,
+                        self.fruits_eaten
+                    ),
+                    BODY_TEXT_SIZE,
+                ));
+            }
+        }
+
+        if let Some(name) = &self.name_entry {
+            lines.push((
+                "New high score! Type your initials:".to_owned(),
+                BODY_TEXT_SIZE,
+            ));
+            lines.push((
+                format!("{:_<width$}", name, width = INITIALS_LENGTH),
+                BODY_TEXT_SIZE,
+            ));
+            lines.push((
+                "Press Enter to confirm, or Esc to skip".to_owned(),
+                BODY_TEXT_SIZE,
+            ));
+
+            return self.draw_panel(context, &lines);
+        }
+
+        if self.replay_saved {
+            lines.push((
+                "Replay saved, press F8 to watch it".to_owned(),
+                BODY_TEXT_SIZE,
+            ));
+        }
+
+        lines.push(("Press H for the high scores".to_owned(), BODY_TEXT_SIZE));
+        lines.push(("Press R to restart".to_owned(), BODY_TEXT_SIZE));
+        lines.push(("Press Esc to quit".to_owned(), BODY_TEXT_SIZE));
+
+        self.draw_panel(context, &lines)
+    }
+
+    /// Darkens the whole window, for text to stand out on top of the board.
+    fn draw_shade(&self, context: &mut Context) -> GameResult {
+        let (width, height) = self.screen_size;
+
+        let shade = Mesh::new_rectangle(
+            context,
+            graphics::DrawMode::fill(),
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                w: width,
+                h: height,
+            },
+            OVERLAY_COLOR,
+        )?;
+
+        graphics::draw(context, &shade, DrawParam::default())
+    }
+
+    /// The lines the start menu is shown in, from the title down to the controls.
+    fn menu_lines(&self) -> Vec<(String, f32)> {
+        vec![
+            ("Snake".to_owned(), TITLE_TEXT_SIZE),
+            (
+                format!(
+                    "Difficulty: < {} >",
+                    capitalise(self.config.difficulty.name())
+                ),
+                BODY_TEXT_SIZE,
+            ),
+            (
+                "Press Left or Right to choose the difficulty".to_owned(),
+                SCORE_TEXT_SIZE,
+            ),
+            ("Press Enter to start".to_owned(), BODY_TEXT_SIZE),
+            ("Press Esc to quit".to_owned(), BODY_TEXT_SIZE),
+        ]
+    }
+
+    /// The lines the scoreboard is shown in, from the title down to the controls.
+    fn scoreboard_lines(&self) -> Vec<(String, f32)> {
+        let mut lines = vec![("High Scores".to_owned(), TITLE_TEXT_SIZE)];
+
+        if self.scoreboard.entries.is_empty() {
+            lines.push(("No scores yet".to_owned(), BODY_TEXT_SIZE));
+        }
+
+        for (index, entry) in self.scoreboard.entries.iter().enumerate() {
+            lines.push((
+                format!("{}. {}  {}", index + 1, entry.name, entry.score),
+                SCORE_TEXT_SIZE,
+            ));
+        }
+
+        lines.push(("Press H to go back".to_owned(), BODY_TEXT_SIZE));
+        lines.push(("Press R to restart".to_owned(), BODY_TEXT_SIZE));
+
+        lines
+    }
+
+    /// Draws `lines` of text of the given sizes one below the other, on a panel across the
+    /// middle of the window.
+    fn draw_panel(&self, context: &mut Context, lines: &[(String, f32)]) -> GameResult {
+        let (width, height) = self.screen_size;
+
+        let content_height: f32 = lines.iter().map(|(_, size)| size * LINE_HEIGHT).sum();
+
+        let panel_bounds = Rect {
+            x: self.config.border_size,
+            y: (height - content_height) / 2.0 - self.config.border_size,
+            w: width - 2.0 * self.config.border_size,
+            h: content_height + 2.0 * self.config.border_size,
+        };
+        let panel = Mesh::new_rectangle(
+            context,
+            graphics::DrawMode::fill(),
+            panel_bounds,
+            PANEL_COLOR,
+        )?;
+        graphics::draw(context, &panel, DrawParam::default())?;
+
+        let mut line_top = panel_bounds.y + self.config.border_size;
+        for (content, size) in lines {
+            let line_height = size * LINE_HEIGHT;
+
+            draw_text(
+                context,
+                content,
+                *size,
+                [width / 2.0, line_top + line_height / 2.0],
+            )?;
+
+            line_top += line_height;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the bar along the top of the window, showing the scores, the lengths of the
+    /// snakes, their combos and how long the game has been running for.
+    fn draw_hud(&self, context: &mut Context) -> GameResult {
+        let (width, _) = self.screen_size;
+
+        let bar = Mesh::new_rectangle(
+            context,
+            graphics::DrawMode::fill(),
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                w: width,
+                h: HUD_HEIGHT,
+            },
+            HUD_COLOR,
+        )?;
+        graphics::draw(context, &bar, DrawParam::default())?;
+
+        // In a timed game, a bar along the bottom of the HUD shrinks as the time to eat the
+        // next fruit in runs out, turning red once it is nearly up.
+        if let GameMode::Timed { seconds_per_fruit } = self.config.mode {
+            let remaining_ms = self.fruit_time_remaining_ms().unwrap_or_default();
+            let fraction = remaining_ms as f32 / (seconds_per_fruit * 1000) as f32;
+
+            if remaining_ms > 0 {
+                let timer = Mesh::new_rectangle(
+                    context,
+                    graphics::DrawMode::fill(),
+                    Rect {
+                        x: 0.0,
+                        y: HUD_HEIGHT - TIMER_BAR_HEIGHT,
+                        w: width * fraction,
+                        h: TIMER_BAR_HEIGHT,
+                    },
+                    if fraction < 0.25 {
+                        DYING_COLOR
+                    } else {
+                        TIMER_BAR_COLOR
+                    },
+                )?;
+                graphics::draw(context, &timer, DrawParam::default())?;
+            }
+        }
+
+        let mut parts = Vec::new();
+
+        match self.snakes.as_slice() {
+            [snake] => {
+                parts.push((format!("Score: {}", snake.score), TEXT_COLOR));
+                parts.push((format!("Best: {}", self.high_score), TEXT_COLOR));
+                parts.push((
+                    format!("Length: {}", 1 + snake.tail_positions.len()),
+                    TEXT_COLOR,
+                ));
+            }
+            snakes => {
+                for (index, snake) in snakes.iter().enumerate() {
+                    parts.push((
+                        format!(
+                            "P{}: {} ({} long)",
+                            index + 1,
+                            snake.score,
+                            1 + snake.tail_positions.len()
+                        ),
+                        snake_colors(index, &self.config.theme).0,
+                    ));
+                }
+            }
+        }
+
+        for (index, snake) in self.snakes.iter().enumerate() {
+            if snake.combo < 2 {
+                continue;
+            }
+
+            let label = match self.snakes.len() {
+                1 => format!("Combo x{}", snake.combo),
+                _ => format!("P{} combo x{}", index + 1, snake.combo),
+            };
+            // The combo flashes on the tick it goes up.
+            let color = if snake.ticks_since_fruit == Some(0) {
+                COMBO_COLOR
+            } else {
+                TEXT_COLOR
+            };
+
+            parts.push((label, color));
+        }
+
+        let clock = match self.time_remaining_ms() {
+            // The time left is rounded up, so that the clock reads 0:00 only once it is up.
+            Some(milliseconds) => {
+                let seconds = milliseconds.div_ceil(1000);
+                format!("Time left: {}:{:02}", seconds / 60, seconds % 60)
+            }
+            None => format!("Time: {}", format_duration(self.elapsed_ms)),
+        };
+        parts.push((clock, TEXT_COLOR));
+        parts.push((capitalise(self.config.difficulty.name()), TEXT_COLOR));
+
+        if let Some((power_up, ticks_remaining)) = self.active_power_up {
+            parts.push((
+                format!("{} {}", capitalise(power_up.name()), ticks_remaining),
+                TEXT_COLOR,
+            ));
+        }
+
+        if self.autopilot {
+            parts.push(("Autopilot".to_owned(), TEXT_COLOR));
+        }
+
+        if self.ghost_mode {
+            parts.push(("Ghost".to_owned(), TEXT_COLOR));
+        }
+
+        let mut fragments = Vec::with_capacity(parts.len() * 2);
+        for (index, (content, color)) in parts.into_iter().enumerate() {
+            if index > 0 {
+                fragments.push(("    ".to_owned(), TEXT_COLOR));
+            }
+            fragments.push((content, color));
+        }
+
+        draw_colored_text(
+            context,
+            &fragments,
+            SCORE_TEXT_SIZE,
+            [width / 2.0, HUD_HEIGHT / 2.0],
+        )
+    }
+
+    /// Ends the game, recording the score if it is a new high score.
+    ///
+    /// High scores are only kept for single-player games.
+    fn end(&mut self, state: State) {
+        self.state = state;
+
+        if let Some(replay) = &self.replay {
+            let scores: Vec<usize> = self.snakes.iter().map(|snake| snake.score).collect();
+
+            if replay.outcome != state || replay.scores != scores {
+                eprintln!(
+                    "The replay went out of sync: the recorded game was {} with scores {:?}, but the replayed one was {} with scores {:?}.",
+                    replay.outcome, replay.scores, state, scores
+                );
+            }
+
+            return;
+        }
+
+        // A game in which the snakes could not crash does not count.
+        if self.used_ghost_mode {
+            return;
+        }
+
+        // Every game that can be played back is recorded, so a good run is never lost.
+        if self.can_save_replay() {
+            self.save_replay();
+        }
+
+        let score = match self.snakes.as_slice() {
+            [snake] => snake.score,
+            _ => return,
+        };
+
+        let record = Record {
+            difficulty: self.config.difficulty,
+            score,
+            length: self.snakes[0].tail_positions.len() + 1,
+            fruits_eaten: self.fruits_eaten,
+            ticks: self.ticks,
+            elapsed_ms: self.elapsed_ms,
+            turns: self.snakes[0].turns,
+        };
+        if let Err(error) = stats::append(&stats::default_path(), &record) {
+            eprintln!("Failed to save the statistics: {}", error);
+        }
+
+        if self.scoreboard.qualifies(score) {
+            self.name_entry = Some(String::new());
+        }
+
+        if score <= self.high_score {
+            return;
+        }
+
+        self.high_score = score;
+
+        if let Err(error) = highscore::save(
+            &self.high_score_path,
+            self.config.difficulty,
+            self.high_score,
+        ) {
+            eprintln!("Failed to save the high score: {}", error);
+        }
+    }
+
+    /// Handles a key pressed while initials are being typed in for the scoreboard, which
+    /// takes the place of the usual controls.
+    ///
+    /// The letters themselves come in through [`event::EventHandler::text_input_event`].
+    fn edit_name(&mut self, keycode: KeyCode) {
+        let name = match &mut self.name_entry {
+            Some(name) => name,
+            None => return,
+        };
+
+        match keycode {
+            KeyCode::Back => {
+                name.pop();
+            }
+            KeyCode::Return if !name.is_empty() => self.submit_name(),
+            // The score is left off the scoreboard.
+            KeyCode::Escape => self.name_entry = None,
+            _ => (),
+        }
+    }
+
+    /// Puts the score of the game that has just ended onto the scoreboard under the initials
+    /// typed in, and shows the scoreboard.
+    fn submit_name(&mut self) {
+        let (name, snake) = match (self.name_entry.take(), self.snakes.as_slice()) {
+            (Some(name), [snake]) => (name, snake),
+            _ => return,
+        };
+
+        self.scoreboard.insert(name, snake.score);
+        self.showing_scoreboard = true;
+
+        if let Err(error) = scoreboard::save(&scoreboard::default_path(), &self.scoreboard) {
+            eprintln!("Failed to save the scoreboard: {}", error);
+        }
+    }
+
+    /// Records the game that has just ended, so that it can be played back.
+    pub fn to_replay(&self) -> Replay {
+        Replay {
+            config: self.config,
+            moves: self.moves.clone(),
+            boosts: self.boosts.clone(),
+            outcome: self.state,
+            scores: self.snakes.iter().map(|snake| snake.score).collect(),
+        }
+    }
+
+    /// Writes the game that has just ended to the replay file, replacing the last one.
+    fn save_replay(&mut self) {
+        let replay = self.to_replay();
+        let path = replay::default_path();
+
+        match replay::save(&path, &replay) {
+            Ok(()) => self.replay_saved = true,
+            Err(error) => eprintln!("Failed to save the replay: {}", error),
+        }
+    }
+
+    /// Whether the game can be saved as a replay, which it can be once it has ended, unless
+    /// it is a replay itself or has been laid out from a level.
+    fn can_save_replay(&self) -> bool {
+        self.state.is_over() && self.replay.is_none() && self.level.is_none() && !self.resumed
+    }
+
+    /// Ends the game once the snakes that crashed have finished blinking.
+    fn finish_dying(&mut self) {
+        if self.snakes.len() == 1 {
+            self.end(State::Lost);
+            return;
+        }
+
+        let survivors: Vec<usize> = (0..self.snakes.len())
+            .filter(|player| !self.crashed.contains(player))
+            .collect();
+        self.end_round(&survivors);
+    }
+
+    /// Ends a game with several players, which is won by the only player left standing, if
+    /// there is one, and is otherwise a draw.
+    fn end_round(&mut self, survivors: &[usize]) {
+        match survivors {
+            [winner] => {
+                self.winner = Some(*winner);
+                self.end(State::Won);
+            }
+            _ => self.end(State::Draw),
+        }
+    }
+
+    /// Ends a game with several players in favour of whoever scored the most, which is a
+    /// draw if several players share the best score.
+    fn end_with_leaders(&mut self) {
+        let best_score = self.snakes.iter().map(|snake| snake.score).max();
+        let leaders: Vec<usize> = (0..self.snakes.len())
+            .filter(|player| Some(self.snakes[*player].score) == best_score)
+            .collect();
+
+        self.end_round(&leaders);
+    }
+
+    /// The time left before a game of time attack ends, which every fruit eaten adds to, or
+    /// `None` if the game is not played against the clock.
+    pub fn time_remaining_ms(&self) -> Option<usize> {
+        match self.config.mode {
+            GameMode::Classic | GameMode::Timed { .. } => None,
+            GameMode::TimeAttack {
+                seconds,
+                bonus_seconds,
+            } => Some(
+                ((seconds + bonus_seconds * self.fruits_eaten) * 1000)
+                    .saturating_sub(self.elapsed_ms),
+            ),
+        }
+    }
+
+    /// The time left to eat the next fruit in before a timed game is lost, or `None` if the
+    /// game is not timed.
+    pub fn fruit_time_remaining_ms(&self) -> Option<usize> {
+        match self.config.mode {
+            GameMode::Timed { seconds_per_fruit } => Some(
+                (seconds_per_fruit * 1000)
+                    .saturating_sub(self.elapsed_ms.saturating_sub(self.last_fruit_ms)),
+            ),
+            GameMode::Classic | GameMode::TimeAttack { .. } => None,
+        }
+    }
+
+    /// Ends a timed game once the time to eat the next fruit in has run out, which the only
+    /// player loses, or whoever scored the most wins if there are several.
+    fn run_out_of_fruit_time(&mut self) {
+        if self.snakes.len() == 1 {
+            self.end(State::Lost);
+        } else {
+            self.end_with_leaders();
+        }
+    }
+
+    /// Ends a game of time attack once its time has run out.
+    fn run_out_of_time(&mut self) {
+        if self.snakes.len() == 1 {
+            self.end(State::TimeUp);
+        } else {
+            self.end_with_leaders();
+        }
+    }
+
+    /// The unoccupied tiles other than those in front of the snakes, in a random order, for
+    /// the layout of the grid to be put on without making the first moves unsafe.
+    fn shuffled_layout_candidates(&mut self) -> Vec<Vector> {
+        let tiles_in_front: Vec<Vector> = self
+            .snakes
+            .iter()
+            .filter_map(|snake| {
+                self.config
+                    .next_position(snake.head_position, snake.movement_direction)
+            })
+            .collect();
+
+        let mut candidates: Vec<Vector> = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|tile| tile.kind == TileKind::Empty && !tiles_in_front.contains(&tile.position))
+            .map(|tile| tile.position)
+            .collect();
+
+        candidates.shuffle(&mut self.rng);
+
+        candidates
+    }
+
+    /// Scatters walls across unoccupied tiles, keeping the tiles in front of the snakes free
+    /// so that their first moves are always safe.
+    fn place_walls(&mut self) {
+        let candidates = self.shuffled_layout_candidates();
+
+        for position in candidates.into_iter().take(self.config.obstacle_count) {
+            self.tiles[position.y][position.x].kind = TileKind::Obstacle;
+            self.walls.push(position);
+        }
+    }
+
+    /// Scatters pairs of portals across unoccupied tiles, keeping the tiles in front of the
+    /// snakes free so that the snakes do not start out being sent across the grid.
+    fn place_portals(&mut self) {
+        // Shuffling draws on the randomness, which would otherwise move the fruits of every
+        // seed played without portals.
+        if self.config.portal_count == 0 {
+            return;
+        }
+
+        let candidates = self.shuffled_layout_candidates();
+
+        for pair in candidates.chunks_exact(2).take(self.config.portal_count) {
+            let (entrance, exit) = (pair[0], pair[1]);
+
+            self.tiles[entrance.y][entrance.x].kind = TileKind::Portal;
+            self.tiles[exit.y][exit.x].kind = TileKind::Portal;
+            self.portals.push((entrance, exit));
+        }
+    }
+
+    /// Places up to `count` fruits, stopping early if the grid runs out of space.
+    ///
+    /// Returns the number of fruits that were placed.
+    fn place_fruits(&mut self, count: usize) -> usize {
+        (0..count)
+            .take_while(|_| self.place_fruit().is_some())
+            .count()
+    }
+
+    /// Places a fruit on a random empty tile, away from the tiles the snakes move onto next
+    /// where possible.
+    fn place_fruit(&mut self) -> Option<Vector> {
+        let position = self.random_fruit_tile()?;
+        self.tiles[position.y][position.x].kind = TileKind::Fruit;
+
+        // Every fruit is a plain one unless special fruits are switched on. A shield cannot
+        // keep a snake from crashing into another that carries on moving, so it is only handed
+        // out to a lone snake.
+        let special_fruits = self.config.special_fruits;
+        let is_single_player = self.snakes.len() == 1;
+        let kind = FRUIT_KIND_WEIGHTS
+            .choose_weighted(&mut self.rng, |(kind, weight)| match kind {
+                FruitKind::Normal => *weight,
+                _ if !special_fruits => 0,
+                FruitKind::Shield if !is_single_player => 0,
+                _ => *weight,
+            })
+            .map_or(FruitKind::Normal, |(kind, _)| *kind);
+
+        self.fruits.push(Fruit { position, kind });
+
+        Some(position)
+    }
+
+    /// Picks a random empty tile for a fruit to be put on, picking again among the others if
+    /// a fruit would not be fair on the one picked.
+    fn random_fruit_tile(&mut self) -> Option<Vector> {
+        let empty_tiles: Vec<Vector> = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|tile| tile.kind == TileKind::Empty)
+            .map(|tile| tile.position)
+            .collect();
+
+        // The tile is picked from all of them first, so that the seeds that never put a fruit
+        // in the way of a snake lay the grid out just as they used to.
+        let position = *empty_tiles.choose(&mut self.rng)?;
+        if self.is_fair_fruit_position(position) {
+            return Some(position);
+        }
+
+        let fair_tiles: Vec<Vector> = empty_tiles
+            .into_iter()
+            .filter(|position| self.is_fair_fruit_position(*position))
+            .collect();
+
+        // On a grid too small to leave the way ahead of the snakes clear, any tile will do.
+        Some(
+            fair_tiles
+                .choose(&mut self.rng)
+                .copied()
+                .unwrap_or(position),
+        )
+    }
+
+    /// Checks whether a fruit on `position` would be fair, which it is unless it lies on the
+    /// tile a snake moves onto next, where it would be eaten without the player doing
+    /// anything.
+    fn is_fair_fruit_position(&self, position: Vector) -> bool {
+        !self.snakes.iter().any(|snake| {
+            self.config
+                .next_position(snake.head_position, snake.next_direction())
+                .map(|next_position| self.portal_exit(next_position))
+                == Some(position)
+        })
+    }
+
+    /// Counts down the lifetime of the bonus fruit, or the cooldown before the next one
+    /// appears if there is none on the grid.
+    ///
+    /// Returns the position of the bonus fruit if it disappeared.
+    fn tick_bonus_fruit(&mut self) -> Option<Vector> {
+        if !self.config.bonus_fruit {
+            return None;
+        }
+
+        match &mut self.bonus_fruit {
+            Some((position, ticks_remaining)) => {
+                *ticks_remaining -= 1;
+
+                if *ticks_remaining == 0 {
+                    let position = *position;
+
+                    self.tiles[position.y][position.x].kind = TileKind::Empty;
+                    self.bonus_fruit = None;
+                    self.bonus_fruit_cooldown = BONUS_FRUIT_COOLDOWN;
+
+                    return Some(position);
+                }
+            }
+            None => {
+                self.bonus_fruit_cooldown = self.bonus_fruit_cooldown.saturating_sub(1);
+
+                if self.bonus_fruit_cooldown == 0 {
+                    self.bonus_fruit = self.random_fruit_tile().map(|position| {
+                        self.tiles[position.y][position.x].kind = TileKind::Fruit;
+                        (position, BONUS_FRUIT_DURATION)
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Counts down the time left for every poisoned tile, clearing up the ones whose time
+    /// has run out, and poisons another tile every so often.
+    fn tick_hazards(&mut self) {
+        if self.config.poison_interval == 0 {
+            return;
+        }
+
+        for (position, ticks_remaining) in &mut self.hazards {
+            *ticks_remaining -= 1;
+
+            if *ticks_remaining == 0 {
+                self.tiles[position.y][position.x].kind = TileKind::Empty;
+            }
+        }
+        self.hazards
+            .retain(|(_, ticks_remaining)| *ticks_remaining > 0);
+
+        self.hazard_cooldown = self.hazard_cooldown.saturating_sub(1);
+        if self.hazard_cooldown > 0 {
+            return;
+        }
+        self.hazard_cooldown = self.config.poison_interval;
+
+        // The tiles next to the heads are left alone, since a snake could not turn away from
+        // poison appearing right in front of it.
+        let tiles_next_to_heads: Vec<Vector> = self
+            .snakes
+            .iter()
+            .flat_map(|snake| {
+                Direction::ALL
+                    .iter()
+                    .filter_map(|direction| {
+                        self.config.next_position(snake.head_position, *direction)
+                    })
+                    .collect::<Vec<Vector>>()
+            })
+            .collect();
+
+        let candidates: Vec<Vector> = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|tile| {
+                tile.kind == TileKind::Empty && !tiles_next_to_heads.contains(&tile.position)
+            })
+            .map(|tile| tile.position)
+            .collect();
+
+        if let Some(position) = candidates.choose(&mut self.rng).copied() {
+            self.tiles[position.y][position.x].kind = TileKind::Poison;
+            self.hazards.push((position, POISON_DURATION));
+        }
+    }
+
+    /// Carries out what a player has asked of the game, whether from the keyboard or a
+    /// gamepad.
+    fn perform(&mut self, context: &mut Context, action: Action) {
+        // Replays are played back without any say from the players, who can only change
+        // how they are looking at them.
+        if self.replay.is_some()
+            && !matches!(
+                action,
+                Action::Quit | Action::Restart | Action::ToggleOverview
+            )
+        {
+            return;
+        }
+
+        match action {
+            Action::Quit => event::quit(context),
+            Action::Save if matches!(self.state, State::Running | State::Countdown(_)) => {
+                self.save_game()
+            }
+            Action::Save => (),
+            Action::Load => self.load_game(),
+            Action::ToggleScoreboard => {
+                if self.state.is_over() {
+                    self.showing_scoreboard = !self.showing_scoreboard;
+                }
+            }
+            Action::WatchReplay => {
+                if let Err(message) = self.play_replay(&replay::default_path()) {
+                    eprintln!("Failed to play the replay: {}", message);
+                }
+            }
+            Action::ToggleFullscreen => self.toggle_fullscreen(context),
+            Action::ToggleOverview => self.toggle_overview(),
+            Action::ToggleAutopilot => self.toggle_autopilot(),
+            Action::ToggleGhostMode => self.toggle_ghost_mode(),
+            Action::ToggleMute => self.set_muted(!self.sounds.muted),
+            Action::NextTheme => {
+                self.config.theme = self.config.theme_overrides.apply(self.config.theme.next());
+                // The checkerboard is drawn in the colours of the theme.
+                self.background = None;
+            }
+            Action::Start | Action::TogglePause if self.state == State::Menu => {
+                self.start_countdown();
+            }
+            Action::Start => (),
+            Action::TogglePause => {
+                if matches!(self.state, State::Running | State::Countdown(_)) {
+                    self.paused = !self.paused;
+                    self.set_boost(false);
+                }
+            }
+            Action::Restart => {
+                if !self.state.is_over() {
+                    return;
+                }
+
+                if let Err(error) = self.restart() {
+                    eprintln!("Failed to restart the game: {}", error);
+                }
+            }
+            Action::Turn(_, direction) if self.state == State::Menu => {
+                self.choose_difficulty(direction)
+            }
+            // Turns made while the game is paused would otherwise pile up unseen.
+            Action::Turn(_, _) if self.paused => (),
+            Action::Turn(player, direction) => self.queue_direction(player, direction),
+            Action::Boost(active) => self.set_boost(active),
+        }
+    }
+
+    /// Speeds the snakes up, or lets them slow down again, keeping how far along they are
+    /// towards their next step.
+    ///
+    /// The snakes can only be sped up while the game is running and not paused.
+    fn set_boost(&mut self, active: bool) {
+        let active = active && self.state == State::Running && !self.paused;
+        if active == self.boost_active {
+            return;
+        }
+
+        let previous_milliseconds_per_frame = self.milliseconds_per_frame();
+        self.boost_active = active;
+
+        // The time built up towards the next step is scaled to the new speed, so that the
+        // snakes neither stall nor take two steps at once when the speed changes.
+        self.ms_since_last_update = self.ms_since_last_update * self.milliseconds_per_frame()
+            / previous_milliseconds_per_frame;
+    }
+
+    /// Queues a turn for the snake of the given player, unless it would reverse the snake
+    /// into itself.
+    fn queue_direction(&mut self, player: usize, direction: Direction) {
+        let snake = match self.snakes.get_mut(player) {
+            Some(snake) => snake,
+            None => return,
+        };
+
+        if snake.queued_directions.len() >= MAX_QUEUED_DIRECTIONS {
+            return;
+        }
+
+        // A turn is judged against the direction the snake will be moving in once
+        // the turns queued before it have been carried out.
+        let last_direction = snake
+            .queued_directions
+            .back()
+            .copied()
+            .unwrap_or(snake.movement_direction);
+
+        if direction == last_direction || direction.is_opposite_of(last_direction) {
+            return;
+        }
+
+        snake.queued_directions.push_back(direction);
+    }
+
+    /// The player the gamepad with the given identifier steers for, assigning the gamepad to
+    /// the next player if it has not been used before.
+    fn gamepad_player(&mut self, id: GamepadId) -> usize {
+        match self.gamepads.iter().position(|gamepad| gamepad.id == id) {
+            Some(player) => player,
+            None => {
+                self.gamepads.push(Gamepad::new(id));
+                self.gamepads.len() - 1
+            }
+        }
+    }
+
+    /// Marks the tiles the snakes cannot move onto without crashing.
+    fn blocked_tiles(&self) -> Vec<Vec<bool>> {
+        let Vector {
+            x: width,
+            y: height,
+        } = self.config.dimensions();
+        let mut blocked = vec![vec![false; width]; height];
+
+        let snake_positions = self.snakes.iter().flat_map(|snake| {
+            std::iter::once(&snake.head_position).chain(snake.tail_positions.iter())
+        });
+
+        // The bot does not know where the portals lead, so it steers clear of them.
+        let portal_positions = self
+            .portals
+            .iter()
+            .flat_map(|(entrance, exit)| [entrance, exit]);
+
+        // Nor does it take its chances with poison, even where it only shrinks the snake.
+        let hazard_positions = self.hazards.iter().map(|(position, _)| position);
+
+        for position in self
+            .walls
+            .iter()
+            .chain(portal_positions)
+            .chain(hazard_positions)
+            .chain(snake_positions)
+        {
+            blocked[position.y][position.x] = true;
+        }
+
+        blocked
+    }
+
+    /// The direction the bot steers the first player's snake in, towards the nearest fruit.
+    fn autopilot_direction(&self) -> Direction {
+        let fruit_positions: Vec<Vector> = self
+            .fruits
+            .iter()
+            .map(|fruit| fruit.position)
+            .chain(self.bonus_fruit.map(|(position, _)| position))
+            .collect();
+
+        bot::next_direction(
+            &self.snakes[0],
+            &self.blocked_tiles(),
+            &fruit_positions,
+            &self.config,
+        )
+    }
+
+    /// The position a snake moving onto `position` ends up at, which is the other end of the
+    /// portal if there is one at `position`.
+    fn portal_exit(&self, position: Vector) -> Vector {
+        self.portals
+            .iter()
+            .find_map(|(entrance, exit)| match position {
+                _ if position == *entrance => Some(*exit),
+                _ if position == *exit => Some(*entrance),
+                _ => None,
+            })
+            .unwrap_or(position)
+    }
+
+    /// Works out what, if anything, the snake of the given player crashes into when the
+    /// snakes move to `next_positions`, where no position stands for having left the grid.
+    fn crash_reason(
+        &self,
+        player: usize,
+        next_positions: &[Option<Vector>],
+    ) -> Option<DeathReason> {
+        let position = match next_positions[player] {
+            Some(position) => position,
+            None => return Some(DeathReason::HitWall),
+        };
+
+        // The tip of a tail moves out of the way on the same tick, so it can be followed
+        // closely without crashing into it.
+        let is_vacated_tail_tip = (0..self.snakes.len())
+            .any(|other| self.vacated_tail_tip(other, next_positions) == Some(position));
+
+        if !is_vacated_tail_tip {
+            if let Some(reason) = self.collision(player, &position) {
+                return Some(reason);
+            }
+        }
+
+        // Meeting another snake head-on is a crash for both.
+        (0..self.snakes.len())
+            .any(|other| other != player && next_positions[other] == Some(position))
+            .then_some(DeathReason::HeadOn)
+    }
+
+    /// The position of the tip of the tail of the given player's snake if it moves away
+    /// when the snakes move to `next_positions`, which it does unless the snake is growing,
+    /// is about to eat a fruit that makes it grow, or is crashing.
+    fn vacated_tail_tip(&self, player: usize, next_positions: &[Option<Vector>]) -> Option<Vector> {
+        let snake = &self.snakes[player];
+        let tip = *snake.tail_positions.back()?;
+        let next_position = next_positions[player]?;
+
+        if snake.pending_growth > 0 {
+            return None;
+        }
+
+        let eats_growing_fruit = self
+            .fruits
+            .iter()
+            .any(|fruit| fruit.position == next_position && fruit.kind != FruitKind::Shrink)
+            || matches!(self.bonus_fruit, Some((position, _)) if position == next_position);
+
+        (!eats_growing_fruit).then_some(tip)
+    }
+
+    /// Works out what the snake of the given player crashes into moving onto `position`, if
+    /// anything, which it does if the tile is taken up by a wall or by any part of a snake.
+    fn collision(&self, player: usize, position: &Vector) -> Option<DeathReason> {
+        match self.tiles[position.y][position.x].kind {
+            TileKind::Occupied if self.snakes[player].occupies(*position) => {
+                Some(DeathReason::HitSelf)
+            }
+            TileKind::Occupied => Some(DeathReason::HitSnake),
+            TileKind::Obstacle => Some(DeathReason::HitObstacle),
+            TileKind::Poison if self.config.poison_effect == PoisonEffect::Deadly => {
+                Some(DeathReason::Poisoned)
+            }
+            TileKind::Empty | TileKind::Fruit | TileKind::Portal | TileKind::Poison => None,
+        }
+    }
+
+    /// Skips the start menu and the countdown, letting the snakes move straight away.
+    pub fn skip_countdown(&mut self) {
+        if matches!(self.state, State::Menu | State::Countdown(_)) {
+            self.state = State::Running;
+            self.ms_since_last_update = 0;
+        }
+    }
+
+    /// The sound the outcome of the game is heard with, if the game has just come to an end
+    /// or a snake has just crashed since it was in the `previous` state.
+    ///
+    /// The crash is heard the moment it happens, rather than once the snakes that crashed
+    /// have finished blinking, and stands for the outcome it leads to, so that only a single
+    /// sound is heard for it.
+    fn outcome_sound(&self, previous: State) -> Option<Sound> {
+        if self.state == previous || matches!(previous, State::Dying(_)) {
+            return None;
+        }
+
+        match self.state {
+            State::Won | State::TimeUp => Some(Sound::Win),
+            // A player left standing wins the game once the others have finished blinking.
+            State::Dying(_) if self.crashed.len() < self.snakes.len() => Some(Sound::Win),
+            State::Dying(_) | State::Lost | State::Draw => Some(Sound::Lose),
+            _ => None,
+        }
+    }
+
+    /// Advances the game by a single tick, turning the first player's snake in the direction
+    /// given, if any, beforehand.
+    ///
+    /// Does nothing unless the game is running, so that the game can be simulated without a
+    /// window, one tick at a time. Stepping while the crashed snakes are blinking skips
+    /// straight to the end of the game.
+    ///
+    /// Returns what happened over the course of the tick.
+    pub fn step(&mut self, input: Option<Direction>) -> Vec<GameEvent> {
+        let state = self.state;
+        let fruit_positions = self.fruit_positions();
+        let mut events = Vec::new();
+
+        self.tick(input, &mut events);
+
+        events.extend(
+            self.fruit_positions()
+                .into_iter()
+                .filter(|position| !fruit_positions.contains(position))
+                .map(|position| GameEvent::FruitPlaced { position }),
+        );
+
+        if self.state.is_over() && !state.is_over() {
+            events.push(GameEvent::Ended {
+                outcome: self.state,
+            });
+        }
+
+        self.log_events(&events);
+
+        events
+    }
+
+    /// Carries out a single tick for [`GameState::step`], adding what happens to `events`.
+    fn tick(&mut self, input: Option<Direction>, events: &mut Vec<GameEvent>) {
+        if let State::Dying(_) = self.state {
+            self.finish_dying();
+        }
+
+        if self.state != State::Running {
+            return;
+        }
+
+        if let Some(direction) = input {
+            self.queue_direction(0, direction);
+        }
+
+        self.active_power_up = self
+            .active_power_up
+            .map(|(power_up, ticks_remaining)| (power_up, ticks_remaining - 1))
+            .filter(|(_, ticks_remaining)| *ticks_remaining > 0);
+
+        if let Some(position) = self.tick_bonus_fruit() {
+            events.push(GameEvent::SpecialFruitExpired { position });
+        }
+        self.tick_hazards();
+        self.tick_combos();
+
+        if self.autopilot {
+            let direction = self.autopilot_direction();
+            let snake = &mut self.snakes[0];
+            snake.queued_directions.clear();
+            snake.queued_directions.push_back(direction);
+        }
+
+        if let Some(replay) = &self.replay {
+            let tick = match replay.moves.get(self.moves.len()) {
+                Some(tick) => tick,
+                // A game of time attack goes on until its time runs out, which is when its
+                // moves do.
+                None if matches!(self.config.mode, GameMode::TimeAttack { .. }) => {
+                    self.run_out_of_time();
+                    return;
+                }
+                // Likewise, a timed game goes on until the time to eat a fruit in runs out.
+                None if matches!(self.config.mode, GameMode::Timed { .. }) => {
+                    self.run_out_of_fruit_time();
+                    return;
+                }
+                None => {
+                    eprintln!("The replay ended before the game did.");
+                    self.state = State::Lost;
+                    return;
+                }
+            };
+
+            for (snake, direction) in self.snakes.iter_mut().zip(tick) {
+                snake.queued_directions.clear();
+                snake.queued_directions.push_back(*direction);
+            }
+        }
+
+        let next_positions: Vec<Option<Vector>> = self
+            .snakes
+            .iter_mut()
+            .map(|snake| {
+                if let Some(direction) = snake.queued_directions.pop_front() {
+                    if direction != snake.movement_direction
+                        && !direction.is_opposite_of(snake.movement_direction)
+                    {
+                        snake.movement_direction = direction;
+                        snake.turns += 1;
+                    }
+                }
+
+                self.config
+                    .next_position(snake.head_position, snake.movement_direction)
+            })
+            .collect();
+
+        // A snake stepping into a portal comes out of the other end, still heading the same
+        // way. It is only sent through once, so that it is not sent straight back, and it is
+        // only checked for crashes where it comes out.
+        let next_positions: Vec<Option<Vector>> = next_positions
+            .into_iter()
+            .map(|position| position.map(|position| self.portal_exit(position)))
+            .collect();
+
+        self.moves.push(
+            self.snakes
+                .iter()
+                .map(|snake| snake.movement_direction)
+                .collect(),
+        );
+        self.ticks += 1;
+
+        // A replay is boosted wherever the game it recorded was, whether or not the boost
+        // key is held while it is played back.
+        let boosted = match &self.replay {
+            Some(replay) => replay
+                .boosts
+                .get(self.boosts.len())
+                .copied()
+                .unwrap_or_default(),
+            None => self.boost_active,
+        };
+        self.boosts.push(boosted);
+
+        // In ghost mode nothing is a crash, and a snake running into the edge of the grid
+        // stays where it is instead.
+        let crashes: Vec<(usize, DeathReason)> = (0..self.snakes.len())
+            .filter(|_| !self.ghost_mode)
+            .filter_map(|player| {
+                self.crash_reason(player, &next_positions)
+                    .map(|reason| (player, reason))
+            })
+            .collect();
+
+        if !crashes.is_empty() {
+            // A shielded snake stops short instead, giving the player time to turn away.
+            if self.snakes.len() == 1 && matches!(self.active_power_up, Some((PowerUp::Shield, _)))
+            {
+                for snake in &mut self.snakes {
+                    snake.previous_positions = snake.positions().collect();
+                }
+
+                return;
+            }
+
+            self.crashed = crashes.iter().map(|(player, _)| *player).collect();
+            events.extend(
+                crashes
+                    .into_iter()
+                    .map(|(player, reason)| GameEvent::Died { player, reason }),
+            );
+            self.state = State::Dying(DYING_FRAMES);
+
+            return;
+        }
+
+        for snake in &mut self.snakes {
+            snake.previous_positions = snake.positions().collect();
+        }
+
+        let mut ate_fruit = false;
+        for (player, position) in next_positions.into_iter().enumerate() {
+            if let Some(position) = position {
+                let from = self.snakes[player].head_position;
+
+                let ate = self.advance(player, position);
+                events.push(GameEvent::Moved {
+                    player,
+                    direction: self.snakes[player].movement_direction,
+                    from,
+                    to: position,
+                });
+
+                if ate {
+                    ate_fruit = true;
+                    self.fruits_eaten += 1;
+                    events.push(GameEvent::AteFruit { player, position });
+                }
+            }
+        }
+
+        if boosted {
+            self.pay_for_boost();
+        }
+
+        debug_assert!(
+            self.is_occupancy_consistent(),
+            "The occupied tiles do not match the snakes and fruits on the grid:\n{}",
+            self.debug_dump()
+        );
+
+        if !ate_fruit {
+            return;
+        }
+
+        self.last_fruit_ms = self.elapsed_ms;
+
+        if let Some(rings) = self.fruits_eaten.checked_div(self.config.shrink_interval) {
+            while self.rings_closed < rings {
+                // Once the arena cannot close in any further, surviving it wins the game.
+                if !self.shrink_border() {
+                    if self.snakes.len() == 1 {
+                        self.end(State::Won);
+                    } else {
+                        self.end_with_leaders();
+                    }
+
+                    return;
+                }
+            }
+        }
+
+        // Fruits that could not be placed earlier for lack of space are made up for as well.
+        self.place_fruits(self.config.fruit_count().saturating_sub(self.fruits.len()));
+
+        // No fruits left means none could be placed, which in turn means that there are no
+        // more unoccupied tiles.
+        if self.fruits.is_empty() && self.bonus_fruit.is_none() {
+            if self.snakes.len() == 1 {
+                self.end(State::Won);
+            } else {
+                // With the board full, whoever scored the most wins.
+                self.end_with_leaders();
+            }
+        }
+    }
+
+    /// Moves the snake of the given player onto `position`, feeding it whatever is there.
+    ///
+    /// Returns whether a fruit was eaten.
+    fn advance(&mut self, player: usize, position: Vector) -> bool {
+        let snake = &mut self.snakes[player];
+
+        snake.move_head(position);
+
+        let eaten_fruit = self
+            .fruits
+            .iter()
+            .position(|fruit| fruit.position == position)
+            .map(|index| self.fruits.swap_remove(index));
+        let ate_bonus_fruit = matches!(
+            self.bonus_fruit,
+            Some((bonus_position, _)) if bonus_position == position
+        );
+
+        // The poison run into, if any, is used up.
+        let ran_into_poison = match self
+            .hazards
+            .iter()
+            .position(|(hazard_position, _)| *hazard_position == position)
+        {
+            Some(index) => {
+                self.hazards.swap_remove(index);
+                true
+            }
+            None => false,
+        };
+
+        // The head takes the tile over from the fruit eaten on it, if any, and the tile is
+        // only freed again once the tail has moved off it.
+        self.tiles[position.y][position.x].kind = TileKind::Occupied;
+
+        // Poison that is deadly can only be run into in ghost mode, which it leaves unharmed.
+        if ran_into_poison && self.config.poison_effect == PoisonEffect::Shrink {
+            self.shrink_tail(player, POISON_SHRINK_LENGTH);
+        }
+
+        let mut points = 0;
+
+        if let Some(fruit) = eaten_fruit {
+            let growth = self.config.growth_per_fruit;
+            let snake = &mut self.snakes[player];
+
+            match fruit.kind {
+                FruitKind::Normal => {
+                    points = 1;
+                    snake.pending_growth += growth;
+                }
+                FruitKind::Golden => {
+                    points = GOLDEN_FRUIT_SCORE;
+                    snake.pending_growth += growth;
+                }
+                // A way out for a long snake rather than a reward, so it scores nothing.
+                FruitKind::Shrink => self.shrink_tail(player, SHRINK_FRUIT_LENGTH),
+                FruitKind::Slow => {
+                    points = 1;
+                    snake.pending_growth += growth;
+                    self.active_power_up = Some((PowerUp::Slowdown, SLOW_FRUIT_DURATION));
+                }
+                FruitKind::Boost => {
+                    points = 1;
+                    snake.pending_growth += growth;
+                    self.active_power_up = Some((PowerUp::SpeedBoost, BOOST_FRUIT_DURATION));
+                }
+                FruitKind::Shield => {
+                    points = 1;
+                    snake.pending_growth += growth;
+                    self.active_power_up = Some((PowerUp::Shield, SHIELD_FRUIT_DURATION));
+                }
+            }
+        }
+
+        if ate_bonus_fruit {
+            self.bonus_fruit = None;
+            self.bonus_fruit_cooldown = BONUS_FRUIT_COOLDOWN;
+            points = BONUS_FRUIT_SCORE;
+            self.snakes[player].pending_growth += BONUS_FRUIT_GROWTH;
+        }
+
+        // Nothing is scored for the shrink fruit, nor does it keep a combo going.
+        if points > 0 {
+            self.score(player, points);
+        }
+
+        // The tail stays in place for as long as the snake is growing.
+        let snake = &mut self.snakes[player];
+        if snake.pending_growth > 0 {
+            snake.pending_growth -= 1;
+        } else {
+            self.shrink_tail(player, 1);
+        }
+
+        eaten_fruit.is_some() || ate_bonus_fruit
+    }
+
+    /// Adds the points for a fruit to the score of the given player, multiplied by their
+    /// combo, which goes up if the fruit was eaten soon enough after the last one.
+    fn score(&mut self, player: usize, points: usize) {
+        let max_combo = self.config.max_combo;
+        let snake = &mut self.snakes[player];
+
+        snake.combo = match snake.ticks_since_fruit {
+            Some(_) => (snake.combo + 1).min(max_combo),
+            None => 1,
+        };
+        snake.best_combo = snake.best_combo.max(snake.combo);
+        snake.ticks_since_fruit = Some(0);
+
+        snake.base_score += points;
+        snake.score += points * snake.combo;
+    }
+
+    /// Counts a tick towards the combo of every snake, ending the combos of those that have
+    /// gone too long without eating.
+    fn tick_combos(&mut self) {
+        for snake in &mut self.snakes {
+            snake.ticks_since_fruit = snake
+                .ticks_since_fruit
+                .map(|ticks| ticks + 1)
+                .filter(|ticks| *ticks <= COMBO_WINDOW_TICKS);
+
+            if snake.ticks_since_fruit.is_none() {
+                snake.combo = 1;
+            }
+        }
+    }
+
+    /// Removes up to `length` pieces from the end of the tail of the snake of the given
+    /// player, freeing their tiles.
+    fn shrink_tail(&mut self, player: usize, length: usize) {
+        for _ in 0..length {
+            match self.snakes[player].pop_tail() {
+                Some(tail_position) => self.free_tile(tail_position),
+                None => break,
+            }
+        }
+    }
+
+    /// Counts a tile moved while boosted, taking a part off the tail of every snake each time
+    /// they have moved as many boosted tiles as a tail part costs, if boosting costs any.
+    ///
+    /// A snake whose tail has been used up carries on with only its head.
+    fn pay_for_boost(&mut self) {
+        let cost = self.config.boost_tail_cost;
+        if cost == 0 {
+            return;
+        }
+
+        self.boosted_tiles += 1;
+        if !self.boosted_tiles.is_multiple_of(cost) {
+            return;
+        }
+
+        for player in 0..self.snakes.len() {
+            self.shrink_tail(player, 1);
+        }
+    }
+
+    /// Closes the outermost open ring of the grid off with walls, taking the fruits on it
+    /// away, or returns `false` if that would leave the arena too small to play in.
+    ///
+    /// The tiles of the ring that snakes lie across are only closed once they have moved off
+    /// them, and the ends of portals are left open.
+    fn shrink_border(&mut self) -> bool {
+        let ring = self.rings_closed;
+        let Vector {
+            x: width,
+            y: height,
+        } = self.config.dimensions();
+
+        if width.min(height) < MIN_ARENA_SIZE + 2 * (ring + 1) {
+            return false;
+        }
+
+        let horizontal_edges = (ring..width - ring).flat_map(|x| {
+            [
+                Vector { x, y: ring },
+                Vector {
+                    x,
+                    y: height - 1 - ring,
+                },
+            ]
+        });
+        let vertical_edges = (ring + 1..height - 1 - ring).flat_map(|y| {
+            [
+                Vector { x: ring, y },
+                Vector {
+                    x: width - 1 - ring,
+                    y,
+                },
+            ]
+        });
+        let positions: Vec<Vector> = horizontal_edges.chain(vertical_edges).collect();
+
+        self.rings_closed += 1;
+
+        for position in positions {
+            match self.tiles[position.y][position.x].kind {
+                TileKind::Empty => (),
+                TileKind::Fruit => {
+                    self.fruits.retain(|fruit| fruit.position != position);
+
+                    if matches!(self.bonus_fruit, Some((bonus_position, _)) if bonus_position == position)
+                    {
+                        self.bonus_fruit = None;
+                        self.bonus_fruit_cooldown = BONUS_FRUIT_COOLDOWN;
+                    }
+                }
+                TileKind::Poison => {
+                    self.hazards
+                        .retain(|(hazard_position, _)| *hazard_position != position);
+                }
+                TileKind::Occupied | TileKind::Obstacle | TileKind::Portal => continue,
+            }
+
+            self.tiles[position.y][position.x].kind = TileKind::Obstacle;
+            self.walls.push(position);
+        }
+
+        true
+    }
+
+    /// Whether `position` lies on one of the rings closed off as the arena shrinks.
+    fn is_outside_arena(&self, position: Vector) -> bool {
+        let Vector {
+            x: width,
+            y: height,
+        } = self.config.dimensions();
+        let distance_to_edge = position
+            .x
+            .min(position.y)
+            .min(width - 1 - position.x)
+            .min(height - 1 - position.y);
+
+        distance_to_edge < self.rings_closed
+    }
+
+    /// Marks the tile at `position` as left by a snake, which leaves the end of a portal or
+    /// a wall behind if there is one there, or closes the tile off if the arena has shrunk
+    /// past it.
+    ///
+    /// A tile that another part of a snake still lies on, such as a head that has just moved
+    /// onto the tip of a tail, stays taken up.
+    fn free_tile(&mut self, position: Vector) {
+        if self.snakes.iter().any(|snake| snake.occupies(position)) {
+            return;
+        }
+
+        let is_portal = self
+            .portals
+            .iter()
+            .any(|(entrance, exit)| position == *entrance || position == *exit);
+
+        // Only a snake in ghost mode can have lain across a wall.
+        self.tiles[position.y][position.x].kind = if self.walls.contains(&position) {
+            TileKind::Obstacle
+        } else if is_portal {
+            TileKind::Portal
+        } else if self.is_outside_arena(position) {
+            self.walls.push(position);
+            TileKind::Obstacle
+        } else {
+            TileKind::Empty
+        };
+    }
+
+    /// Checks that the tiles marked as taken up by snakes, fruits and poison match the
+    /// snakes, fruits and poisoned tiles on the grid.
+    fn is_occupancy_consistent(&self) -> bool {
+        let count_tiles = |kind: TileKind| {
+            self.tiles
+                .iter()
+                .flatten()
+                .filter(|tile| tile.kind == kind)
+                .count()
+        };
+
+        // Snakes in ghost mode may lie across each other, so every tile is only counted once.
+        let snake_tiles: HashSet<Vector> = self.snakes.iter().flat_map(Snake::positions).collect();
+        let fruit_count = self.fruits.len() + usize::from(self.bonus_fruit.is_some());
+
+        // The parts counted on the tiles every snake lies across must add up to its length.
+        let are_parts_counted = self.snakes.iter().all(|snake| {
+            snake.occupied_positions.values().sum::<usize>() == snake.tail_positions.len() + 1
+                && snake.positions().all(|position| snake.occupies(position))
+        });
+
+        are_parts_counted
+            && count_tiles(TileKind::Occupied) == snake_tiles.len()
+            && count_tiles(TileKind::Fruit) == fruit_count
+            && count_tiles(TileKind::Poison) == self.hazards.len()
+    }
+
+    /// Draws the grid as text, one line per row, with `H` for the heads of the snakes, `T` for
+    /// their tails, `F` for fruits, `X` for poison, `#` for walls, `O` for the ends of
+    /// portals and `.` for empty tiles.
+    pub fn debug_dump(&self) -> String {
+        let Vector {
+            x: width,
+            y: height,
+        } = self.config.dimensions();
+        let mut grid = vec![vec!['.'; width]; height];
+
+        let fruit_positions = self
+            .fruits
+            .iter()
+            .map(|fruit| fruit.position)
+            .chain(self.bonus_fruit.map(|(position, _)| position));
+        let marked_positions = self
+            .walls
+            .iter()
+            .map(|wall| (*wall, '#'))
+            .chain(
+                self.portals
+                    .iter()
+                    .flat_map(|(entrance, exit)| [(*entrance, 'O'), (*exit, 'O')]),
+            )
+            .chain(fruit_positions.map(|position| (position, 'F')))
+            .chain(self.hazards.iter().map(|(position, _)| (*position, 'X')))
+            .chain(self.snakes.iter().flat_map(|snake| {
+                let tail = snake.tail_positions.iter().map(|position| (*position, 'T'));
+
+                std::iter::once((snake.head_position, 'H')).chain(tail)
+            }));
+
+        for (position, mark) in marked_positions {
+            grid[position.y][position.x] = mark;
+        }
+
+        let rows: Vec<String> = grid.into_iter().map(String::from_iter).collect();
+
+        rows.join("\n")
+    }
+
+    /// The state the game is in.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The snakes on the grid, one for each player.
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    /// The fruits on the grid, apart from the bonus fruit.
+    pub fn fruits(&self) -> &[Fruit] {
+        &self.fruits
+    }
+
+    /// The time the game has been running for, not counting the countdown, the pauses or the
+    /// time after it ended.
+    pub fn elapsed_ms(&self) -> usize {
+        self.elapsed_ms
+    }
+
+    /// The difficulty the game is played at.
+    pub fn difficulty(&self) -> Difficulty {
+        self.config.difficulty
+    }
+
+    /// The time that passes between two movements of the snakes, which gets shorter with
+    /// every fruit eaten, depending on the difficulty.
+    pub fn milliseconds_per_frame(&self) -> usize {
+        let milliseconds = self
+            .config
+            .initial_milliseconds_per_frame()
+            .saturating_sub(self.config.speed_ramp() * self.fruits_eaten)
+            .max(MIN_MILLISECONDS_PER_FRAME);
+
+        let milliseconds = match self.active_power_up {
+            Some((PowerUp::Slowdown, _)) => milliseconds * SLOW_FRAME_PERCENTAGE / 100,
+            Some((PowerUp::SpeedBoost, _)) => milliseconds * BOOST_FRAME_PERCENTAGE / 100,
+            Some((PowerUp::Shield, _)) | None => milliseconds,
+        };
+
+        if self.boost_active {
+            milliseconds * HELD_BOOST_FRAME_PERCENTAGE / 100
+        } else {
+            milliseconds
+        }
+    }
+
+    /// Lets the time since the last frame pass, counting down, blinking the snakes that
+    /// crashed or moving the snakes once enough of it has built up.
+    fn advance_clock(&mut self, context: &mut Context) {
+        if self.paused {
+            return;
+        }
+
+        // The time is measured in whole milliseconds, with what is left over carried over,
+        // so that rounding every frame down does not slow the game down over time.
+        let delta = delta(context) + self.delta_remainder;
+        self.delta_remainder = Duration::from_nanos(u64::from(delta.subsec_nanos() % 1_000_000));
+        let delta_ms = usize::try_from(delta.as_millis()).unwrap_or(usize::MAX);
+
+        match self.state {
+            // The clock follows the real time rather than the ticks, so it runs smoothly.
+            State::Running => {
+                self.elapsed_ms = self.elapsed_ms.saturating_add(delta_ms);
+
+                // Replays are played back tick by tick rather than against the clock, so they
+                // run out of time when they run out of moves instead.
+                if self.replay.is_none() && self.time_remaining_ms() == Some(0) {
+                    self.run_out_of_time();
+                    self.log_events(&[GameEvent::Ended {
+                        outcome: self.state,
+                    }]);
+                    return;
+                }
+
+                if self.replay.is_none() && self.fruit_time_remaining_ms() == Some(0) {
+                    self.run_out_of_fruit_time();
+                    self.log_events(&[GameEvent::Ended {
+                        outcome: self.state,
+                    }]);
+                    return;
+                }
+            }
+            State::Countdown(count) => {
+                self.ms_since_last_update += delta_ms.min(MILLISECONDS_PER_COUNT);
+                if self.ms_since_last_update < MILLISECONDS_PER_COUNT {
+                    return;
+                }
+                self.ms_since_last_update -= MILLISECONDS_PER_COUNT;
+
+                self.state = match count {
+                    0 | 1 => State::Running,
+                    _ => State::Countdown(count - 1),
+                };
+
+                return;
+            }
+            State::Dying(frames) => {
+                self.ms_since_last_update += delta_ms.min(MILLISECONDS_PER_DYING_FRAME);
+                if self.ms_since_last_update < MILLISECONDS_PER_DYING_FRAME {
+                    return;
+                }
+                self.ms_since_last_update -= MILLISECONDS_PER_DYING_FRAME;
+
+                match frames {
+                    0 | 1 => {
+                        self.finish_dying();
+                        self.log_events(&[GameEvent::Ended {
+                            outcome: self.state,
+                        }]);
+                    }
+                    _ => self.state = State::Dying(frames - 1),
+                }
+
+                return;
+            }
+            State::Menu | State::Won | State::Lost | State::Draw | State::TimeUp => return,
+        }
+
+        self.catch_up(delta_ms);
+    }
+
+    /// Lets `delta_ms` more milliseconds pass in the running game, taking as many steps as
+    /// fit into the time built up since the last one, and returns how many were taken.
+    ///
+    /// Each step takes one turn from each snake's queue, so the turns made during a slow frame
+    /// are carried out one after another.
+    fn catch_up(&mut self, delta_ms: usize) -> usize {
+        // The snakes make up for a slow frame with as many steps as fit into it, but a long
+        // stall, such as while the window switches in or out of fullscreen, is cut short so
+        // that they do not race across the grid afterwards.
+        self.ms_since_last_update = catch_up_ms(
+            self.ms_since_last_update,
+            delta_ms,
+            self.milliseconds_per_frame(),
+        );
+
+        // The time per step is worked out again after every step, as eating a fruit changes
+        // it. Once a snake crashes, no more steps are taken, so that it is seen blinking.
+        let mut steps = 0;
+        while self.state == State::Running
+            && self.ms_since_last_update >= self.milliseconds_per_frame()
+        {
+            self.ms_since_last_update -= self.milliseconds_per_frame();
+            self.step(None);
+            steps += 1;
+        }
+
+        steps
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn key_down_event(
+        &mut self,
+        context: &mut Context,
+        keycode: event::KeyCode,
+        _: event::KeyMods,
+        repeat: bool,
+    ) {
+        // Holding Backspace down keeps rubbing out letters.
+        if self.name_entry.is_some() {
+            self.edit_name(keycode);
+            return;
+        }
+
+        // A key held down only counts once, so that holding a toggle does not flick it on and
+        // off, and holding a direction does not keep queueing it.
+        if repeat {
+            return;
+        }
+
+        if let Some(action) = key_action(keycode) {
+            self.perform(context, action);
+        }
+    }
+
+    fn key_up_event(&mut self, context: &mut Context, keycode: event::KeyCode, _: event::KeyMods) {
+        // Only the boost lasts for as long as its key is held.
+        if let Some(Action::Boost(_)) = key_action(keycode) {
+            self.perform(context, Action::Boost(false));
+        }
+    }
+
+    fn text_input_event(&mut self, _: &mut Context, character: char) {
+        if let Some(name) = &mut self.name_entry {
+            if character.is_ascii_alphanumeric() && name.len() < INITIALS_LENGTH {
+                name.push(character.to_ascii_uppercase());
+            }
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, context: &mut Context, button: Button, id: GamepadId) {
+        // A gamepad plugged in partway through the game is taken on by the next player the
+        // first time one of its buttons is pressed.
+        let player = self.gamepad_player(id);
+
+        if let Some(action) = button_action(button, player) {
+            self.perform(context, action);
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, context: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        // A gamepad is only given a player once its stick is pushed past the dead zone, so
+        // that an idle or drifting stick does not take up a player's place.
+        let is_bound = self.gamepads.iter().any(|gamepad| gamepad.id == id);
+        if !is_bound && value.abs() < STICK_DEADZONE {
+            return;
+        }
+
+        let player = self.gamepad_player(id);
+        let gamepad = &mut self.gamepads[player];
+
+        // The left stick steers the snake in whichever direction it is pushed furthest in.
+        match axis {
+            Axis::LeftStickX => gamepad.stick_position.0 = value,
+            Axis::LeftStickY => gamepad.stick_position.1 = value,
+            _ => return,
+        }
+
+        let (x, y) = gamepad.stick_position;
+        let direction = if x.abs().max(y.abs()) < STICK_DEADZONE {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if y > 0.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
+        };
+
+        // Holding the stick in place turns the snake only once.
+        if direction == gamepad.stick_direction {
+            return;
+        }
+        gamepad.stick_direction = direction;
+
+        if let Some(direction) = direction {
+            self.perform(context, Action::Turn(player, direction));
+        }
+    }
+
+    fn update(&mut self, context: &mut Context) -> GameResult {
+        let state = self.state;
+        let fruits_eaten = self.fruits_eaten;
+
+        self.advance_clock(context);
+
+        if self.fruits_eaten > fruits_eaten {
+            self.sounds.play(context, Sound::Eat);
+        }
+
+        if let Some(sound) = self.outcome_sound(state) {
+            self.sounds.play(context, sound);
+        }
+
+        Ok(())
+    }
+
+    fn quit_event(&mut self, _: &mut Context) -> bool {
+        // Whatever has been recorded of a game that has not ended yet is kept as well.
+        if let Some(event_log) = &mut self.event_log {
+            if let Err(error) = event_log.flush() {
+                eprintln!("Failed to save the event log: {}", error);
+            }
+        }
+
+        false
+    }
+
+    fn resize_event(&mut self, context: &mut Context, width: f32, height: f32) {
+        // The drawing area grows and shrinks with the window instead of being stretched.
+        let screen = Rect {
+            x: 0.0,
+            y: 0.0,
+            w: width,
+            h: height,
+        };
+
+        if let Err(error) = graphics::set_screen_coordinates(context, screen) {
+            eprintln!("Failed to resize the game: {}", error);
+            return;
+        }
+
+        self.screen_size = (width, height);
+    }
+
+    fn draw(&mut self, context: &mut Context) -> GameResult {
+        graphics::clear(context, self.config.theme.background);
+
+        let layout = self.layout();
+
+        // The checkerboard only changes with the layout, so it is built once and kept until
+        // the window is resized.
+        let background = match self.background.take() {
+            Some((built_for, mesh)) if built_for == layout => mesh,
+            _ => background_mesh(context, &self.config, &layout, self.overview)?,
+        };
+        graphics::draw(context, &background, DrawParam::default())?;
+        self.background = Some((layout, background));
+
+        // The head, the fruit and the tail change every frame, so they are gathered into
+        // a single mesh to be submitted with one draw call.
+        let mut builder = MeshBuilder::new();
+
+        // In the overview, everything fills the whole of its tile.
+        let overview = self.overview;
+        let size = |detailed_size: f32| if overview { 1.0 } else { detailed_size };
+
+        for wall in &self.walls {
+            add_tile(&mut builder, wall, WALL_COLOR, 1.0, &layout)?;
+        }
+
+        for (entrance, exit) in &self.portals {
+            add_tile(&mut builder, entrance, PORTAL_COLOR, size(0.8), &layout)?;
+            add_tile(&mut builder, exit, PORTAL_COLOR, size(0.8), &layout)?;
+        }
+
+        for fruit in &self.fruits {
+            add_tile(
+                &mut builder,
+                &fruit.position,
+                fruit_color(fruit.kind, &self.config.theme),
+                size(0.4),
+                &layout,
+            )?;
+        }
+
+        for (position, ticks_remaining) in &self.hazards {
+            // Fades away as it is about to clear up.
+            let color = Color {
+                a: (*ticks_remaining as f32 / POISON_FADE_TICKS as f32).min(1.0),
+                ..POISON_COLOR
+            };
+
+            add_tile(&mut builder, position, color, size(0.9), &layout)?;
+        }
+
+        if let Some((position, ticks_remaining)) = &self.bonus_fruit {
+            // Pulses from tick to tick to draw attention to itself.
+            let size = size(if ticks_remaining % 2 == 0 { 0.6 } else { 0.45 });
+
+            add_tile(&mut builder, position, BONUS_FRUIT_COLOR, size, &layout)?;
+        }
+
+        // The snakes slide from their previous positions into their current ones over the
+        // course of a tick.
+        let progress = match self.state {
+            State::Running => {
+                (self.ms_since_last_update as f32 / self.milliseconds_per_frame() as f32).min(1.0)
+            }
+            _ => 1.0,
+        };
+
+        for (player, snake) in self.snakes.iter().enumerate() {
+            // The snakes that crashed blink red until the game ends.
+            let (head_color, tail_colors) = match self.state {
+                State::Dying(frames) if frames % 2 == 0 && self.crashed.contains(&player) => {
+                    (DYING_COLOR, (DYING_COLOR, DYING_COLOR))
+                }
+                _ => snake_colors(player, &self.config.theme),
+            };
+
+            let points: Vec<(f32, f32)> = snake
+                .positions()
+                .enumerate()
+                .map(
+                    |(index, position)| match snake.previous_positions.get(index) {
+                        Some(previous) => slide(previous, &position, progress),
+                        None => (position.x as f32, position.y as f32),
+                    },
+                )
+                .collect();
+
+            // The tail darkens from the piece right behind the head down to the tip, except
+            // in the overview, where it is drawn in a single colour.
+            let last_index = snake.tail_positions.len().saturating_sub(1).max(1);
+            let tail_color = |index: usize| {
+                if overview {
+                    tail_colors.0
+                } else {
+                    lerp_color(
+                        tail_colors.0,
+                        tail_colors.1,
+                        index as f32 / last_index as f32,
+                    )
+                }
+            };
+
+            // The gaps between neighbouring pieces are filled in first, so that the pieces
+            // drawn over them leave only the gaps showing, and the body reads as one piece.
+            for (index, pair) in points.windows(2).enumerate() {
+                if let Some(bounds) = connector_bounds(pair[0], pair[1], size(0.5), &layout) {
+                    builder.rectangle(graphics::DrawMode::fill(), bounds, tail_color(index))?;
+                }
+            }
+
+            for (index, point) in points.iter().skip(1).enumerate() {
+                builder.rectangle(
+                    graphics::DrawMode::fill(),
+                    point_bounds(*point, size(0.5), &layout),
+                    tail_color(index),
+                )?;
+            }
+
+            // The head goes on top, so that the tail does not cover its eyes.
+            if let Some(head_point) = points.first() {
+                if overview {
+                    builder.rectangle(
+                        graphics::DrawMode::fill(),
+                        point_bounds(*head_point, 1.0, &layout),
+                        head_color,
+                    )?;
+                } else {
+                    add_head(&mut builder, snake, *head_point, head_color, &layout)?;
+                }
+            }
+        }
+
+        // The fog lifts once the game is over, so that the whole board can be seen.
+        if self.fog_of_war && !self.state.is_over() && self.state != State::Menu {
+            self.add_fog(&mut builder, &layout)?;
+        }
+
+        let mesh = builder.build(context)?;
+        graphics::draw(context, &mesh, DrawParam::default())?;
+
+        self.draw_hud(context)?;
+        self.draw_overlay(context)?;
+
+        graphics::present(context)?;
+        Ok(())
+    }
+}
+
+/// The size of the window required to fit the grid set up by `config`, its border and the
+/// HUD, with the tiles scaled down from the size set up if the window would otherwise be
+/// larger than [`MAX_WINDOW_WIDTH`] by [`MAX_WINDOW_HEIGHT`].
+fn window_size(config: &GameConfig) -> (f32, f32) {
+    let dimensions = config.dimensions();
+    let columns = dimensions.x as f32;
+    let rows = dimensions.y as f32;
+    let border_size = config.border_size;
+
+    let tile_size = config
+        .tile_size
+        .min((MAX_WINDOW_WIDTH - 2.0 * border_size) / columns)
+        .min((MAX_WINDOW_HEIGHT - 2.0 * border_size - HUD_HEIGHT) / rows);
+
+    (
+        columns * tile_size + 2.0 * border_size,
+        rows * tile_size + 2.0 * border_size + HUD_HEIGHT,
+    )
+}
+
+/// Builds the checkerboard of tiles the grid is drawn on, framed by a border and with thin
+/// lines between the tiles, or a single flat block of colour for the overview.
+fn background_mesh(
+    context: &mut Context,
+    config: &GameConfig,
+    layout: &Layout,
+    overview: bool,
+) -> GameResult<Mesh> {
+    let mut builder = MeshBuilder::new();
+
+    let dimensions = config.dimensions();
+    let border_size = config.border_size;
+
+    let grid_width = dimensions.x as f32 * layout.tile_size;
+    let grid_height = dimensions.y as f32 * layout.tile_size;
+
+    if overview {
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect {
+                x: layout.origin.0,
+                y: layout.origin.1,
+                w: grid_width,
+                h: grid_height,
+            },
+            config.theme.tile_dark,
+        )?;
+
+        return builder.build(context);
+    }
+
+    // The border is a single rectangle behind the tiles, which leave only its edge showing.
+    builder.rectangle(
+        graphics::DrawMode::fill(),
+        Rect {
+            x: layout.origin.0 - border_size,
+            y: layout.origin.1 - border_size,
+            w: grid_width + 2.0 * border_size,
+            h: grid_height + 2.0 * border_size,
+        },
+        GRID_LINE_COLOR,
+    )?;
+
+    for i in 0..dimensions.x {
+        for j in 0..dimensions.y {
+            let is_even = (j * 10 + i) % 2 == (j % 2);
+
+            add_tile(
+                &mut builder,
+                &Vector { x: i, y: j },
+                if is_even {
+                    config.theme.tile_dark
+                } else {
+                    config.theme.tile_light
+                },
+                1.0,
+                layout,
+            )?;
+        }
+    }
+
+    for i in 1..dimensions.x {
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect {
+                x: layout.origin.0 + i as f32 * layout.tile_size - GRID_LINE_WIDTH / 2.0,
+                y: layout.origin.1,
+                w: GRID_LINE_WIDTH,
+                h: grid_height,
+            },
+            GRID_LINE_COLOR,
+        )?;
+    }
+
+    for j in 1..dimensions.y {
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect {
+                x: layout.origin.0,
+                y: layout.origin.1 + j as f32 * layout.tile_size - GRID_LINE_WIDTH / 2.0,
+                w: grid_width,
+                h: GRID_LINE_WIDTH,
+            },
+            GRID_LINE_COLOR,
+        )?;
+    }
+
+    builder.build(context)
+}
+
+/// The time built up towards the next steps once `delta_ms` more has passed on top of the
+/// `built_up_ms` already built up, cut short at the time [`MAX_CATCH_UP_TICKS`] steps of
+/// `milliseconds_per_frame` each take.
+fn catch_up_ms(built_up_ms: usize, delta_ms: usize, milliseconds_per_frame: usize) -> usize {
+    built_up_ms
+        .saturating_add(delta_ms)
+        .min(MAX_CATCH_UP_TICKS * milliseconds_per_frame)
+}
+
+/// Writes out a number of milliseconds as minutes and seconds, leaving out the part of a
+/// second that has not passed in full.
+fn format_duration(milliseconds: usize) -> String {
+    let seconds = milliseconds / 1000;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Turns the first letter of `word` into a capital.
+fn capitalise(word: &str) -> String {
+    let mut characters = word.chars();
+
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+        None => String::new(),
+    }
+}
+
+/// The colour of the head of the given player's snake, and the colours its tail fades between.
+fn snake_colors(player: usize, theme: &Theme) -> (Color, (Color, Color)) {
+    match player {
+        0 => (theme.head, (theme.tail_light, theme.tail_dark)),
+        _ => (
+            SECOND_HEAD_COLOR,
+            (SECOND_TAIL_COLOR_HEAD, SECOND_TAIL_COLOR_TIP),
+        ),
+    }
+}
+
+/// Blends `from` into `to`, where a `progress` of 0 gives `from` and 1 gives `to`.
+fn lerp_color(from: Color, to: Color, progress: f32) -> Color {
+    let lerp = |from: f32, to: f32| from + (to - from) * progress;
+
+    Color {
+        r: lerp(from.r, to.r),
+        g: lerp(from.g, to.g),
+        b: lerp(from.b, to.b),
+        a: lerp(from.a, to.a),
+    }
+}
+
+/// The colour in which a fruit of the given kind is drawn.
+fn fruit_color(kind: FruitKind, theme: &Theme) -> Color {
+    match kind {
+        FruitKind::Normal => theme.fruit,
+        FruitKind::Golden => GOLDEN_FRUIT_COLOR,
+        FruitKind::Shrink => SHRINK_FRUIT_COLOR,
+        FruitKind::Slow => SLOW_FRUIT_COLOR,
+        FruitKind::Boost => BOOST_FRUIT_COLOR,
+        FruitKind::Shield => SHIELD_FRUIT_COLOR,
+    }
+}
+
+/// Adds a tile to the mesh being built instead of drawing it straight away.
+fn add_tile(
+    builder: &mut MeshBuilder,
+    position: &Vector,
+    color: Color,
+    size: f32,
+    layout: &Layout,
+) -> GameResult {
+    builder.rectangle(
+        graphics::DrawMode::fill(),
+        tile_bounds(position, size, layout),
+        color,
+    )?;
+
+    Ok(())
+}
+
+/// Adds the snake's head to the mesh being built, with a pair of eyes looking in its
+/// direction of movement.
+fn add_head(
+    builder: &mut MeshBuilder,
+    snake: &Snake,
+    point: (f32, f32),
+    color: Color,
+    layout: &Layout,
+) -> GameResult {
+    let head = point_bounds(point, 0.7, layout);
+    builder.rectangle(graphics::DrawMode::fill(), head, color)?;
+
+    // The eyes are placed relative to the bounds of the head, so they stay within it.
+    let center = (head.x + head.w / 2.0, head.y + head.h / 2.0);
+
+    // The eyes sit towards the front of the head, one on either side of its centre line.
+    // They look where the snake is about to turn, so a queued turn shows up straight away.
+    let (forward, sideways) = match snake.next_direction() {
+        Direction::Up => ((0.0, -1.0), (1.0, 0.0)),
+        Direction::Down => ((0.0, 1.0), (1.0, 0.0)),
+        Direction::Left => ((-1.0, 0.0), (0.0, 1.0)),
+        Direction::Right => ((1.0, 0.0), (0.0, 1.0)),
+    };
+    let offset = 0.22 * head.w;
+    let radius = 0.1 * head.w;
+
+    for side in [-1.0, 1.0] {
+        let eye = [
+            center.0 + (forward.0 + side * sideways.0) * offset,
+            center.1 + (forward.1 + side * sideways.1) * offset,
+        ];
+
+        builder.circle(graphics::DrawMode::fill(), eye, radius, 0.1, EYE_COLOR)?;
+    }
+
+    Ok(())
+}
+
+/// Computes the on-screen bounds of a tile, scaled down by `size` around its centre.
+fn tile_bounds(position: &Vector, size: f32, layout: &Layout) -> Rect {
+    point_bounds((position.x as f32, position.y as f32), size, layout)
+}
+
+/// Computes the on-screen bounds of a tile-sized square at a point on the grid that may lie
+/// between tiles, scaled down by `size` around its centre.
+fn point_bounds(point: (f32, f32), size: f32, layout: &Layout) -> Rect {
+    let tile_size = size * layout.tile_size;
+    let padding_size = layout.tile_size - tile_size;
+
+    Rect {
+        x: padding_size / 2.0 + layout.origin.0 + point.0 * layout.tile_size,
+        y: padding_size / 2.0 + layout.origin.1 + point.1 * layout.tile_size,
+        w: tile_size,
+        h: tile_size,
+    }
+}
+
+/// Computes the on-screen bounds of the strip joining the tile-sized squares at two points
+/// on the grid, scaled down by `size` around their centres, or `None` if the points are not
+/// next to each other, such as when a snake has wrapped around the edge of the grid or
+/// passed through a portal.
+fn connector_bounds(from: (f32, f32), to: (f32, f32), size: f32, layout: &Layout) -> Option<Rect> {
+    // Neighbouring pieces stay a tile apart along the grid lines while sliding, give or take
+    // some rounding.
+    if (from.0 - to.0).abs() + (from.1 - to.1).abs() > 1.001 {
+        return None;
+    }
+
+    let from = point_bounds(from, size, layout);
+    let to = point_bounds(to, size, layout);
+
+    let x = from.x.min(to.x);
+    let y = from.y.min(to.y);
+
+    Some(Rect {
+        x,
+        y,
+        w: (from.x + from.w).max(to.x + to.w) - x,
+        h: (from.y + from.h).max(to.y + to.h) - y,
+    })
+}
+
+/// The point on the grid `progress` of the way from `from` to `to`.
+///
+/// Pieces that did not move to a neighbouring tile, such as when wrapping around the edge
+/// of the grid, jump straight to `to` instead of sliding across the whole grid.
+fn slide(from: &Vector, to: &Vector, progress: f32) -> (f32, f32) {
+    if from.x.abs_diff(to.x) + from.y.abs_diff(to.y) != 1 {
+        return (to.x as f32, to.y as f32);
+    }
+
+    (
+        from.x as f32 + (to.x as f32 - from.x as f32) * progress,
+        from.y as f32 + (to.y as f32 - from.y as f32) * progress,
+    )
+}
+
+/// The action a key asks for, if it asks for any.
+fn key_action(keycode: KeyCode) -> Option<Action> {
+    let action = match keycode {
+        KeyCode::Escape => Action::Quit,
+        KeyCode::F5 => Action::Save,
+        KeyCode::F9 => Action::Load,
+        KeyCode::F8 => Action::WatchReplay,
+        KeyCode::Return => Action::Start,
+        KeyCode::H => Action::ToggleScoreboard,
+        KeyCode::F11 => Action::ToggleFullscreen,
+        KeyCode::Tab => Action::ToggleOverview,
+        KeyCode::B => Action::ToggleAutopilot,
+        KeyCode::G => Action::ToggleGhostMode,
+        KeyCode::M => Action::ToggleMute,
+        KeyCode::T => Action::NextTheme,
+        KeyCode::P => Action::TogglePause,
+        KeyCode::R => Action::Restart,
+        KeyCode::LShift | KeyCode::RShift => Action::Boost(true),
+        KeyCode::Up => Action::Turn(0, Direction::Up),
+        KeyCode::Down => Action::Turn(0, Direction::Down),
+        KeyCode::Left => Action::Turn(0, Direction::Left),
+        KeyCode::Right => Action::Turn(0, Direction::Right),
+        KeyCode::W => Action::Turn(1, Direction::Up),
+        KeyCode::S => Action::Turn(1, Direction::Down),
+        KeyCode::A => Action::Turn(1, Direction::Left),
+        KeyCode::D => Action::Turn(1, Direction::Right),
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// The action a button on the gamepad of the given player asks for, if it asks for any.
+///
+/// The directional pad steers the snake just like the arrow keys do.
+fn button_action(button: Button, player: usize) -> Option<Action> {
+    let action = match button {
+        Button::DPadUp => Action::Turn(player, Direction::Up),
+        Button::DPadDown => Action::Turn(player, Direction::Down),
+        Button::DPadLeft => Action::Turn(player, Direction::Left),
+        Button::DPadRight => Action::Turn(player, Direction::Right),
+        Button::Start => Action::TogglePause,
+        Button::South => Action::Restart,
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Draws a line of text centred on the given point.
+fn draw_text(context: &mut Context, content: &str, size: f32, center: [f32; 2]) -> GameResult {
+    draw_colored_text(context, &[(content.to_owned(), TEXT_COLOR)], size, center)
+}
+
+/// Draws a line of text made up of parts in different colours, centred on the given point.
+fn draw_colored_text(
+    context: &mut Context,
+    parts: &[(String, Color)],
+    size: f32,
+    center: [f32; 2],
+) -> GameResult {
+    let mut text = Text::default();
+    for (content, color) in parts {
+        text.add(
+            TextFragment::new(content.as_str())
+                .color(*color)
+                .scale(PxScale::from(size)),
+        );
+    }
+    let dimensions = text.dimensions(context);
+
+    graphics::draw(
+        context,
+        &text,
+        DrawParam::default().dest([
+            center[0] - dimensions.w / 2.0,
+            center[1] - dimensions.h / 2.0,
+        ]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_log::{Line, LoggedEvent};
+
+    use std::fs;
+
+    /// The most ticks a game in the tests is let run for before it is taken to be stuck.
+    const MAX_TICKS: usize = 10_000;
+
+    /// Creates a seeded game on a grid of the given size, skipping the menu and the countdown
+    /// so that it can be stepped straight away.
+    fn running_game(width: usize, height: usize, seed: u64) -> GameState {
+        let mut state = GameState::new_with_seed(
+            Vector {
+                x: width,
+                y: height,
+            },
+            seed,
+        )
+        .unwrap();
+        state.skip_countdown();
+
+        state
+    }
+
+    /// Creates a seeded single-player game on an empty square grid of the given size, with
+    /// the snake's tail the given number of parts long, and takes the fruits off the grid so
+    /// that they can be put where they are wanted.
+    fn empty_game(size: usize, tail_length: usize) -> GameState {
+        empty_game_for(1, size, tail_length)
+    }
+
+    /// Creates a game like [`empty_game`] does, for the given number of players.
+    fn empty_game_for(player_count: usize, size: usize, tail_length: usize) -> GameState {
+        let mut state = GameState::new(GameConfig {
+            width: Some(size),
+            height: Some(size),
+            initial_tail_length: tail_length,
+            player_count,
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+
+        take_fruits(&mut state);
+        state.skip_countdown();
+
+        state
+    }
+
+    /// Takes every fruit off the grid.
+    fn take_fruits(state: &mut GameState) {
+        for fruit in std::mem::take(&mut state.fruits) {
+            state.tiles[fruit.position.y][fruit.position.x].kind = TileKind::Empty;
+        }
+    }
+
+    /// Puts a normal fruit on the empty tile at `position`.
+    fn put_fruit(state: &mut GameState, position: Vector) {
+        state.tiles[position.y][position.x].kind = TileKind::Fruit;
+        state.fruits.push(Fruit {
+            position,
+            kind: FruitKind::Normal,
+        });
+    }
+
+    /// Puts a wall on the empty tile at `position`.
+    fn put_wall(state: &mut GameState, position: Vector) {
+        state.tiles[position.y][position.x].kind = TileKind::Obstacle;
+        state.walls.push(position);
+    }
+
+    /// Steps `state` without any input until the game is over.
+    fn finish(state: &mut GameState) {
+        for _ in 0..MAX_TICKS {
+            if state.state.is_over() {
+                return;
+            }
+
+            state.step(None);
+        }
+
+        panic!(
+            "The game was still {} after {} ticks.",
+            state.state, MAX_TICKS
+        );
+    }
+
+    fn scores(state: &GameState) -> Vec<usize> {
+        state.snakes.iter().map(|snake| snake.score).collect()
+    }
+
+    /// The direction that takes the first player's snake closest to the first fruit without
+    /// crashing, if there is one.
+    fn towards_fruit(state: &GameState) -> Option<Direction> {
+        let snake = &state.snakes[0];
+        let fruit = state.fruits.first()?.position;
+
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| !direction.is_opposite_of(snake.movement_direction))
+            .filter_map(|direction| {
+                let position = state.config.next_position(snake.head_position, direction)?;
+
+                state
+                    .collision(0, &position)
+                    .is_none()
+                    .then_some((direction, position))
+            })
+            .min_by_key(|(_, position)| position.x.abs_diff(fruit.x) + position.y.abs_diff(fruit.y))
+            .map(|(direction, _)| direction)
+    }
+
+    #[test]
+    fn saved_game_is_resumed_as_it_was() {
+        let mut state = running_game(12, 12, 3);
+        state.set_autopilot(true);
+        for _ in 0..40 {
+            state.step(None);
+        }
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.save");
+        state.save(&path).unwrap();
+
+        let mut resumed = GameState::load(&path).unwrap();
+        assert_eq!(resumed.snapshot(), state.snapshot());
+
+        // Both games pick their randomness up from the same seed, so they carry on alike.
+        for _ in 0..40 {
+            state.step(None);
+            resumed.step(None);
+        }
+        assert_eq!(resumed.snapshot(), state.snapshot());
+    }
+
+    #[test]
+    fn shrunken_arena_is_resumed_as_it_was() {
+        let mut state = running_game(12, 12, 3);
+        state.config.shrink_interval = 2;
+        state.fruits_eaten = 3;
+
+        // Rings closed are kept as they are rather than worked out from the fruits eaten.
+        assert!(state.shrink_border());
+        assert!(state.shrink_border());
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.save");
+        state.save(&path).unwrap();
+
+        let mut resumed = GameState::load(&path).unwrap();
+        assert_eq!(resumed.rings_closed, 2);
+        assert_eq!(resumed.snapshot(), state.snapshot());
+    }
+
+    #[test]
+    fn boosting_costs_a_tail_part_every_few_boosted_tiles() {
+        let mut state = empty_game(11, 3);
+        state.config.boost_tail_cost = 2;
+
+        state.set_boost(true);
+        let mut lengths = Vec::new();
+        for _ in 0..4 {
+            state.step(None);
+            lengths.push(state.snakes[0].tail_positions.len());
+        }
+        assert_eq!(lengths, [3, 2, 2, 1]);
+
+        // Moving without the boost costs nothing.
+        state.set_boost(false);
+        state.step(Some(Direction::Up));
+        state.step(None);
+        assert_eq!(state.snakes[0].tail_positions.len(), 1);
+
+        // Once the tail has been used up, the snake carries on with only its head.
+        state.set_boost(true);
+        for _ in 0..3 {
+            state.step(None);
+        }
+        assert!(state.snakes[0].tail_positions.is_empty());
+        assert_eq!(state.state, State::Running);
+    }
+
+    #[test]
+    fn boosted_game_is_replayed_in_sync() {
+        let mut state = running_game(12, 12, 5);
+        state.config.boost_tail_cost = 1;
+        state.snakes[0].pending_growth = 4;
+
+        let mut lengths = Vec::new();
+        for tick in 0..6 {
+            state.set_boost(tick % 3 == 0);
+            state.step(towards_fruit(&state));
+            lengths.push(state.snakes[0].tail_positions.len());
+        }
+
+        // The replay is played back without the boost key being held.
+        let mut replayed = GameState::from_replay(state.to_replay()).unwrap();
+        replayed.skip_countdown();
+        replayed.snakes[0].pending_growth = 4;
+
+        let mut replayed_lengths = Vec::new();
+        for _ in 0..6 {
+            replayed.step(None);
+            replayed_lengths.push(replayed.snakes[0].tail_positions.len());
+        }
+        assert_eq!(replayed_lengths, lengths);
+        assert_eq!(
+            replayed.snakes[0].head_position,
+            state.snakes[0].head_position
+        );
+    }
+
+    #[test]
+    fn save_with_a_fruit_eaten_after_its_time_is_rejected() {
+        let mut state = running_game(12, 12, 3);
+        state.elapsed_ms = 5_000;
+
+        let mut saved = state.snapshot();
+        saved.last_fruit_ms = 6_000;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.save");
+        save::save(&path, &saved).unwrap();
+
+        assert!(GameState::load(&path).is_err());
+    }
+
+    #[test]
+    fn stepped_game_is_replayed_move_for_move() {
+        // The snake is steered towards the fruits until it has eaten a few, and then left to
+        // run into whatever is in its way.
+        let mut state = running_game(10, 10, 11);
+        let mut positions = Vec::new();
+        while !state.state.is_over() && positions.len() < MAX_TICKS {
+            let input = if state.snakes[0].score < 3 {
+                towards_fruit(&state)
+            } else {
+                None
+            };
+            state.step(input);
+            positions.push(state.snakes[0].head_position);
+        }
+
+        let recorded = state.to_replay();
+        assert!(recorded.scores[0] > 0);
+
+        let mut replayed = GameState::from_replay(recorded.clone()).unwrap();
+        replayed.skip_countdown();
+        let mut replayed_positions = Vec::new();
+        while !replayed.state.is_over() && replayed_positions.len() < MAX_TICKS {
+            replayed.step(None);
+            replayed_positions.push(replayed.snakes[0].head_position);
+        }
+
+        assert_eq!(replayed_positions, positions);
+        assert_eq!(replayed.state, recorded.outcome);
+        assert_eq!(scores(&replayed), recorded.scores);
+    }
+
+    #[test]
+    fn replay_ends_with_the_recorded_score_and_state() {
+        // The bot eats a few fruits before the snake is left to run into the edge of the grid.
+        let mut state = running_game(12, 12, 7);
+        state.set_autopilot(true);
+        for _ in 0..60 {
+            state.step(None);
+        }
+        state.set_autopilot(false);
+        finish(&mut state);
+
+        let recorded = state.to_replay();
+        assert!(recorded.scores[0] > 0);
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("test.replay");
+        replay::save(&path, &recorded).unwrap();
+
+        let mut replayed = GameState::from_replay(replay::load(&path).unwrap()).unwrap();
+        replayed.skip_countdown();
+        finish(&mut replayed);
+
+        assert_eq!(replayed.state, recorded.outcome);
+        assert_eq!(scores(&replayed), recorded.scores);
+    }
+
+    #[test]
+    fn games_with_the_same_seed_play_out_the_same_way() {
+        let mut first = running_game(10, 10, 42);
+        let mut second = running_game(10, 10, 42);
+        assert_eq!(first.fruits, second.fruits);
+
+        while !first.state.is_over() {
+            let input = towards_fruit(&first);
+            first.step(input);
+            second.step(input);
+
+            assert_eq!(first.fruits, second.fruits);
+            assert_eq!(first.snakes, second.snakes);
+        }
+
+        assert!(first.fruits_eaten > 0);
+        assert_eq!(second.state, first.state);
+    }
+
+    #[test]
+    fn default_game_plays_like_the_original() {
+        let mut state = GameState::new(GameConfig {
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+        assert_eq!(state.difficulty(), Difficulty::Normal);
+        assert_eq!(state.tiles.len(), 10);
+        assert_eq!(state.tiles[0].len(), 10);
+        assert_eq!(state.fruits.len(), 1);
+        assert!(state.snakes[0].tail_positions.is_empty());
+
+        state.fruits_eaten = 5;
+        assert_eq!(state.milliseconds_per_frame(), 300);
+
+        state.place_fruits(20);
+        assert!(state
+            .fruits
+            .iter()
+            .all(|fruit| fruit.kind == FruitKind::Normal));
+
+        for _ in 0..BONUS_FRUIT_COOLDOWN * 2 {
+            state.tick_bonus_fruit();
+        }
+        assert_eq!(state.bonus_fruit, None);
+    }
+
+    #[test]
+    fn special_and_bonus_fruits_can_be_switched_on() {
+        let mut state = GameState::new(GameConfig {
+            special_fruits: true,
+            bonus_fruit: true,
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+
+        state.place_fruits(20);
+        assert!(state
+            .fruits
+            .iter()
+            .any(|fruit| fruit.kind != FruitKind::Normal));
+
+        for _ in 0..BONUS_FRUIT_COOLDOWN {
+            state.tick_bonus_fruit();
+        }
+        assert!(state.bonus_fruit.is_some());
+    }
+
+    #[test]
+    fn each_difficulty_sets_up_a_grid_of_its_own() {
+        let presets = [
+            (Difficulty::Easy, 12, 3),
+            (Difficulty::Normal, 10, 1),
+            (Difficulty::Hard, 8, 1),
+        ];
+
+        for (difficulty, size, fruit_count) in presets {
+            let state = GameState::new(GameConfig {
+                difficulty,
+                seed: Some(1),
+                ..GameConfig::default()
+            })
+            .unwrap();
+            assert_eq!(state.tiles.len(), size);
+            assert_eq!(state.tiles[0].len(), size);
+            assert_eq!(state.fruits.len(), fruit_count);
+        }
+
+        // A size or fruit count that was chosen is kept whatever the difficulty.
+        let state = GameState::new(GameConfig {
+            difficulty: Difficulty::Hard,
+            width: Some(20),
+            fruit_count: Some(4),
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+        assert_eq!(state.tiles.len(), 8);
+        assert_eq!(state.tiles[0].len(), 20);
+        assert_eq!(state.fruits.len(), 4);
+    }
+
+    #[test]
+    fn snakes_stay_put_in_the_menu() {
+        let mut state = GameState::new_with_seed(Vector { x: 10, y: 10 }, 1).unwrap();
+        assert_eq!(state.state, State::Menu);
+        let head = state.snakes[0].head_position;
+
+        assert!(state.step(Some(Direction::Up)).is_empty());
+        assert_eq!(state.catch_up(10_000), 0);
+        assert_eq!(state.snakes[0].head_position, head);
+        assert_eq!(state.ticks, 0);
+        assert_eq!(state.state, State::Menu);
+    }
+
+    #[test]
+    fn snakes_stay_put_during_the_countdown() {
+        let mut state = GameState::new_with_seed(Vector { x: 10, y: 10 }, 1).unwrap();
+        state.start_countdown();
+        assert_eq!(state.state, State::Countdown(COUNTDOWN_SECONDS));
+        let head = state.snakes[0].head_position;
+
+        assert!(state.step(Some(Direction::Up)).is_empty());
+        assert_eq!(state.snakes[0].head_position, head);
+        assert_eq!(state.ticks, 0);
+        assert_eq!(state.state, State::Countdown(COUNTDOWN_SECONDS));
+    }
+
+    #[test]
+    fn choosing_a_difficulty_in_the_menu_sets_up_its_grid() {
+        let mut state = GameState::new(GameConfig {
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+        assert_eq!(state.state, State::Menu);
+
+        state.choose_difficulty(Direction::Left);
+        assert_eq!(state.difficulty(), Difficulty::Easy);
+        assert_eq!(state.state, State::Menu);
+        assert_eq!(state.tiles.len(), 12);
+        assert_eq!(state.fruits.len(), 3);
+    }
+
+    #[test]
+    fn each_difficulty_moves_the_snakes_at_its_own_speed() {
+        let presets = [
+            (Difficulty::Easy, 400, 400),
+            (Difficulty::Normal, 300, 300),
+            (Difficulty::Hard, 200, 180),
+        ];
+
+        for (difficulty, milliseconds, after_two_fruits) in presets {
+            let mut state = GameState::new(GameConfig {
+                difficulty,
+                seed: Some(1),
+                ..GameConfig::default()
+            })
+            .unwrap();
+            assert_eq!(state.difficulty(), difficulty);
+            assert_eq!(state.milliseconds_per_frame(), milliseconds);
+
+            state.fruits_eaten = 2;
+            assert_eq!(state.milliseconds_per_frame(), after_two_fruits);
+        }
+    }
+
+    #[test]
+    fn snake_moves_one_tile_in_each_direction() {
+        let mut state = empty_game(11, 2);
+        let mut from = state.snakes[0].head_position;
+        assert_eq!(from, Vector { x: 5, y: 5 });
+
+        // The snake goes round in a square, back onto the tile it started on.
+        let moves = [
+            (Direction::Up, Vector { x: 5, y: 4 }),
+            (Direction::Left, Vector { x: 4, y: 4 }),
+            (Direction::Down, Vector { x: 4, y: 5 }),
+            (Direction::Right, Vector { x: 5, y: 5 }),
+        ];
+
+        for (direction, to) in moves {
+            let events = state.step(Some(direction));
+
+            assert!(events.contains(&GameEvent::Moved {
+                player: 0,
+                direction,
+                from,
+                to,
+            }));
+            assert_eq!(state.snakes[0].head_position, to);
+            assert_eq!(state.snakes[0].movement_direction, direction);
+            from = to;
+        }
+
+        assert_eq!(state.state, State::Running);
+    }
+
+    #[test]
+    fn snake_running_into_the_edge_loses_the_game() {
+        let mut state = empty_game(7, 2);
+
+        // The head starts in the middle of the grid, three tiles short of the edge.
+        for _ in 0..3 {
+            state.step(None);
+        }
+        assert_eq!(state.state, State::Running);
+
+        let events = state.step(None);
+        assert!(events.contains(&GameEvent::Died {
+            player: 0,
+            reason: DeathReason::HitWall,
+        }));
+
+        let events = state.step(None);
+        assert_eq!(state.state, State::Lost);
+        assert!(events.contains(&GameEvent::Ended {
+            outcome: State::Lost,
+        }));
+    }
+
+    #[test]
+    fn snake_grows_by_one_after_eating() {
+        let mut state = empty_game(11, 2);
+        let length = state.snakes[0].positions().count();
+        put_fruit(&mut state, Vector { x: 6, y: 5 });
+
+        let events = state.step(None);
+        assert!(events.contains(&GameEvent::AteFruit {
+            player: 0,
+            position: Vector { x: 6, y: 5 },
+        }));
+
+        state.step(None);
+        assert_eq!(state.snakes[0].positions().count(), length + 1);
+
+        // The snake stops growing once it has grown by the fruit it ate.
+        state.step(None);
+        assert_eq!(state.snakes[0].positions().count(), length + 1);
+    }
+
+    #[test]
+    fn occupied_tiles_follow_the_snake_as_it_eats_and_grows() {
+        let mut state = running_game(8, 8, 9);
+
+        while state.fruits_eaten < 5 {
+            let input = towards_fruit(&state);
+            state.step(input);
+            assert_eq!(state.state, State::Running);
+
+            let taken = state
+                .tiles
+                .iter()
+                .flatten()
+                .filter(|tile| matches!(tile.kind, TileKind::Occupied | TileKind::Fruit))
+                .count();
+            let fruits = state.fruits.len() + usize::from(state.bonus_fruit.is_some());
+            assert_eq!(taken, state.snakes[0].positions().count() + fruits);
+        }
+    }
+
+    #[test]
+    fn snake_growing_by_three_parts_takes_three_ticks_to_grow() {
+        // The snake heads right from the middle of the grid, straight into a fruit.
+        let mut state = empty_game(11, 2);
+        state.config.growth_per_fruit = 3;
+        put_fruit(&mut state, Vector { x: 6, y: 5 });
+
+        let mut lengths = vec![state.snakes[0].tail_positions.len()];
+        state.step(None);
+        lengths.push(state.snakes[0].tail_positions.len());
+        assert_eq!(state.fruits_eaten, 1);
+
+        // The fruit put down in place of the one eaten is taken off again, so that it cannot
+        // be eaten as well.
+        take_fruits(&mut state);
+
+        for _ in 0..3 {
+            state.step(None);
+            lengths.push(state.snakes[0].tail_positions.len());
+        }
+
+        // The tail grows by a part on the tick the fruit is eaten and on the two after it,
+        // and then stops growing.
+        assert_eq!(lengths, [2, 3, 4, 5, 5]);
+        assert_eq!(state.snakes[0].pending_growth, 0);
+    }
+
+    #[test]
+    fn snake_running_into_itself_loses_the_game() {
+        let mut state = empty_game(11, 4);
+
+        state.step(Some(Direction::Up));
+        state.step(Some(Direction::Left));
+        let events = state.step(Some(Direction::Down));
+        assert!(events.contains(&GameEvent::Died {
+            player: 0,
+            reason: DeathReason::HitSelf,
+        }));
+
+        state.step(None);
+        assert_eq!(state.state, State::Lost);
+    }
+
+    #[test]
+    fn snake_chasing_its_tail_moves_onto_the_tile_it_leaves() {
+        // The snake curls up into a square, with its head next to the tip of its tail.
+        let mut state = empty_game(11, 3);
+        state.step(Some(Direction::Up));
+        state.step(Some(Direction::Left));
+        assert_eq!(
+            state.snakes[0].tail_positions.back(),
+            Some(&Vector { x: 4, y: 5 })
+        );
+
+        // The tip moves off its tile on the same tick as the head moves onto it.
+        for direction in [Direction::Down, Direction::Right, Direction::Up] {
+            let events = state.step(Some(direction));
+            assert!(!events
+                .iter()
+                .any(|event| matches!(event, GameEvent::Died { .. })));
+            assert_eq!(state.state, State::Running);
+        }
+        assert_eq!(state.snakes[0].head_position, Vector { x: 5, y: 4 });
+    }
+
+    #[test]
+    fn growing_snake_chasing_its_tail_runs_into_it() {
+        let mut state = empty_game(11, 3);
+        state.step(Some(Direction::Up));
+        state.step(Some(Direction::Left));
+
+        // A growing snake's tail stays where it is, so the head runs into its tip.
+        state.snakes[0].pending_growth = 1;
+        let events = state.step(Some(Direction::Down));
+        assert!(events.contains(&GameEvent::Died {
+            player: 0,
+            reason: DeathReason::HitSelf,
+        }));
+    }
+
+    #[test]
+    fn snake_in_ghost_mode_passes_through_its_own_tail() {
+        let mut state = empty_game(11, 4);
+        state.toggle_ghost_mode();
+
+        // The head moves onto the middle of the tail, which would otherwise crash the snake.
+        state.step(Some(Direction::Up));
+        state.step(Some(Direction::Left));
+        let events = state.step(Some(Direction::Down));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Died { .. })));
+        assert_eq!(state.snakes[0].head_position, Vector { x: 4, y: 5 });
+        assert!(state.snakes[0]
+            .tail_positions
+            .contains(&Vector { x: 4, y: 5 }));
+
+        // The tile the head and tail shared stays taken until the tail has moved off it too.
+        for _ in 0..3 {
+            state.step(None);
+            assert_eq!(state.state, State::Running);
+
+            let taken: HashSet<Vector> = state.snakes[0].positions().collect();
+            for tile in state.tiles.iter().flatten() {
+                assert_eq!(
+                    tile.kind == TileKind::Occupied,
+                    taken.contains(&tile.position)
+                );
+            }
+        }
+        assert_eq!(state.snakes[0].head_position, Vector { x: 4, y: 8 });
+    }
+
+    #[test]
+    fn arena_closes_in_as_the_fruits_are_eaten() {
+        // The snake heads right from the middle of the grid, straight into a fruit.
+        let mut state = empty_game(9, 0);
+        state.config.shrink_interval = 1;
+        put_fruit(&mut state, Vector { x: 5, y: 4 });
+
+        state.step(None);
+        assert_eq!(state.fruits_eaten, 1);
+        assert_eq!(state.rings_closed, 1);
+        assert_eq!(state.state, State::Running);
+
+        // The outermost ring of tiles is walled off, and the fruit eaten is replaced inside
+        // what is left of the arena.
+        for tile in state.tiles.iter().flatten() {
+            let Vector { x, y } = tile.position;
+            let on_edge = x == 0 || y == 0 || x == 8 || y == 8;
+            assert_eq!(tile.kind == TileKind::Obstacle, on_edge);
+        }
+        assert_eq!(state.fruits.len(), 1);
+        assert!(!state.is_outside_arena(state.fruits[0].position));
+    }
+
+    #[test]
+    fn snake_surviving_the_smallest_arena_wins_the_game() {
+        // On a 7x7 grid, the arena can close in by two rings before it is as small as it gets.
+        let mut state = empty_game(7, 0);
+        state.config.shrink_interval = 1;
+
+        let moves = [
+            (Direction::Right, Vector { x: 4, y: 3 }),
+            (Direction::Up, Vector { x: 4, y: 2 }),
+            (Direction::Left, Vector { x: 3, y: 2 }),
+        ];
+        let mut free_tiles = Vec::new();
+        let mut events = Vec::new();
+        for (direction, fruit) in moves {
+            take_fruits(&mut state);
+            put_fruit(&mut state, fruit);
+            events = state.step(Some(direction));
+
+            let free = state
+                .tiles
+                .iter()
+                .flatten()
+                .filter(|tile| matches!(tile.kind, TileKind::Empty | TileKind::Fruit))
+                .count();
+            free_tiles.push(free);
+        }
+
+        // The free tiles drop as the rings close and the snake grows, leaving 23 of the 5x5
+        // arena and then 6 of the 3x3 one. The arena then cannot close in any further, and
+        // surviving it wins the game.
+        assert_eq!(state.rings_closed, 2);
+        assert_eq!(free_tiles[..2], [23, 6]);
+        assert_eq!(state.state, State::Won);
+        assert!(events.contains(&GameEvent::Ended {
+            outcome: State::Won,
+        }));
+    }
+
+    #[test]
+    fn snake_running_into_a_wall_loses_the_game() {
+        // The snake heads right from the middle of the grid, two tiles short of the wall.
+        let mut state = empty_game(11, 2);
+        put_wall(&mut state, Vector { x: 7, y: 5 });
+
+        state.step(None);
+        assert_eq!(state.state, State::Running);
+
+        let events = state.step(None);
+        assert!(events.contains(&GameEvent::Died {
+            player: 0,
+            reason: DeathReason::HitObstacle,
+        }));
+        assert!(matches!(state.state, State::Dying(_)));
+
+        let events = state.step(None);
+        assert_eq!(state.state, State::Lost);
+        assert!(events.contains(&GameEvent::Ended {
+            outcome: State::Lost,
+        }));
+    }
+
+    #[test]
+    fn no_fruit_is_placed_on_a_full_grid() {
+        let mut state = empty_game(5, 2);
+
+        for tile in state.tiles.iter_mut().flatten() {
+            if tile.kind == TileKind::Empty {
+                tile.kind = TileKind::Obstacle;
+            }
+        }
+
+        assert_eq!(state.place_fruit(), None);
+        assert!(state.fruits.is_empty());
+    }
+
+    #[test]
+    fn snake_filling_the_grid_wins_the_game() {
+        // The snake starts out lying across the fourth row, heading right.
+        let mut state = GameState::new(GameConfig {
+            width: Some(6),
+            height: Some(6),
+            initial_tail_length: 2,
+            seed: Some(1),
+            ..GameConfig::default()
+        })
+        .unwrap();
+        state.skip_countdown();
+        let snake = &state.snakes[0];
+        assert_eq!(snake.head_position, Vector { x: 3, y: 3 });
+        assert_eq!(
+            snake.tail_positions,
+            [Vector { x: 2, y: 3 }, Vector { x: 1, y: 3 }]
+        );
+
+        // The snake goes round and round a path through every tile, zigzagging up the grid
+        // from the bottom row and going back down the first column, so it eats every fruit
+        // without ever running into itself.
+        let along_the_path = |position: Vector| {
+            let rightwards = position.y % 2 == 1;
+
+            match position {
+                Vector { x: 0, y: 5 } => Direction::Right,
+                Vector { x: 0, .. } => Direction::Down,
+                Vector { y: 0, .. } => Direction::Left,
+                Vector { x: 5, .. } if rightwards => Direction::Up,
+                Vector { x: 1, .. } if !rightwards => Direction::Up,
+                _ if rightwards => Direction::Right,
+                _ => Direction::Left,
+            }
+        };
+
+        let mut events = Vec::new();
+        for _ in 0..MAX_TICKS {
+            if state.state.is_over() {
+                break;
+            }
+
+            let direction = along_the_path(state.snakes[0].head_position);
+            events = state.step(Some(direction));
+        }
+
+        assert_eq!(state.state, State::Won);
+        assert!(events.contains(&GameEvent::Ended {
+            outcome: State::Won,
+        }));
+        assert!(state.fruits.is_empty());
+        assert!(!state
+            .tiles
+            .iter()
+            .flatten()
+            .any(|tile| tile.kind == TileKind::Empty));
+    }
+
+    #[test]
+    fn snake_running_into_another_loses_to_it() {
+        // The first snake starts at 2,2 heading right, and the second at 8,8 heading left.
+        let mut state = empty_game_for(2, 11, 4);
+
+        // The first snake heads down the fourth column, reaching the row the second snake is
+        // crossing once the second snake's head has gone past it.
+        state.step(None);
+        for _ in 0..5 {
+            state.step(Some(Direction::Down));
+        }
+        assert_eq!(state.snakes[0].head_position, Vector { x: 3, y: 7 });
+        assert_eq!(state.snakes[1].head_position, Vector { x: 2, y: 8 });
+
+        let events = state.step(None);
+        assert!(events.contains(&GameEvent::Died {
+            player: 0,
+            reason: DeathReason::HitSnake,
+        }));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Died { player: 1, .. })));
+
+        state.step(None);
+        assert_eq!(state.state, State::Won);
+        assert_eq!(state.winner, Some(1));
+    }
+
+    #[test]
+    fn one_sound_is_heard_for_each_outcome() {
+        // The first snake runs into the second, as in the test above, and the second wins.
+        let mut state = empty_game_for(2, 11, 4);
+        state.step(None);
+        for _ in 0..5 {
+            state.step(Some(Direction::Down));
+        }
+
+        let mut sounds = Vec::new();
+        while !state.state.is_over() {
+            let previous = state.state;
+            state.step(None);
+            sounds.extend(state.outcome_sound(previous));
+        }
+        assert_eq!(state.state, State::Won);
+        assert_eq!(sounds, [Sound::Win]);
+
+        // A lone snake running off the edge of the grid is only heard losing.
+        let mut state = empty_game(7, 2);
+        let mut sounds = Vec::new();
+        while !state.state.is_over() {
+            let previous = state.state;
+            state.step(None);
+            sounds.extend(state.outcome_sound(previous));
+        }
+        assert_eq!(state.state, State::Lost);
+        assert_eq!(sounds, [Sound::Lose]);
+    }
+
+    #[test]
+    fn snakes_meeting_head_on_draw() {
+        let mut state = empty_game_for(2, 11, 2);
+
+        // The first snake heads down the third column, which the second snake reaches along
+        // the ninth row on the same tick.
+        for _ in 0..5 {
+            state.step(Some(Direction::Down));
+        }
+        assert_eq!(state.snakes[0].head_position, Vector { x: 2, y: 7 });
+        assert_eq!(state.snakes[1].head_position, Vector { x: 3, y: 8 });
+
+        let events = state.step(None);
+        for player in 0..2 {
+            assert!(events.contains(&GameEvent::Died {
+                player,
+                reason: DeathReason::HeadOn,
+            }));
+        }
+
+        state.step(None);
+        assert_eq!(state.state, State::Draw);
+        assert_eq!(state.winner, None);
+    }
+
+    #[test]
+    fn long_frame_is_cut_short_to_a_few_steps() {
+        for milliseconds_per_frame in [400, 300, 200, MIN_MILLISECONDS_PER_FRAME] {
+            for delta_ms in [900, 5_000, usize::MAX] {
+                let steps =
+                    catch_up_ms(0, delta_ms, milliseconds_per_frame) / milliseconds_per_frame;
+                let missed_steps = delta_ms / milliseconds_per_frame;
+                assert_eq!(steps, missed_steps.min(MAX_CATCH_UP_TICKS));
+            }
+
+            // Time already built up towards the next step counts towards the limit as well.
+            let built_up_ms = catch_up_ms(milliseconds_per_frame - 1, 900, milliseconds_per_frame);
+            assert!(built_up_ms / milliseconds_per_frame <= MAX_CATCH_UP_TICKS);
+        }
+
+        // A short frame is kept in full.
+        assert_eq!(catch_up_ms(100, 150, 300), 250);
+    }
+
+    #[test]
+    fn long_frame_is_made_up_for_with_a_few_steps_at_most() {
+        // The snake starts in the middle of the grid heading right, with room to spare.
+        let mut state = empty_game_for(1, 21, 2);
+        let start = state.snakes[0].head_position;
+
+        let steps = state.catch_up(100 * state.milliseconds_per_frame());
+        assert_eq!(steps, MAX_CATCH_UP_TICKS);
+        assert_eq!(
+            state.snakes[0].head_position,
+            Vector {
+                x: start.x + MAX_CATCH_UP_TICKS,
+                y: start.y,
+            }
+        );
+    }
+
+    #[test]
+    fn turns_queued_during_a_long_frame_are_taken_one_per_step() {
+        let mut state = empty_game_for(1, 21, 2);
+        let start = state.snakes[0].head_position;
+        state.queue_direction(0, Direction::Up);
+        state.queue_direction(0, Direction::Left);
+
+        assert_eq!(state.catch_up(3 * state.milliseconds_per_frame()), 3);
+        assert_eq!(
+            state.snakes[0].head_position,
+            Vector {
+                x: start.x - 2,
+                y: start.y - 1,
+            }
+        );
+    }
+
+    #[test]
+    fn catching_up_stops_once_a_snake_crashes() {
+        // The head starts in the middle of the grid, three tiles short of the edge.
+        let mut state = empty_game_for(1, 7, 2);
+        for _ in 0..3 {
+            state.step(None);
+        }
+
+        let steps = state.catch_up(MAX_CATCH_UP_TICKS * state.milliseconds_per_frame());
+        assert_eq!(steps, 1);
+        assert!(matches!(state.state, State::Dying(_)));
+    }
+
+    #[test]
+    fn two_turns_within_a_tick_are_taken_over_the_next_two_ticks() {
+        let mut state = empty_game(11, 2);
+
+        // Heading right, the snake is turned up and then left before it moves.
+        state.queue_direction(0, Direction::Up);
+        state.queue_direction(0, Direction::Left);
+        assert_eq!(
+            state.snakes[0].queued_directions,
+            [Direction::Up, Direction::Left]
+        );
+
+        state.step(None);
+        assert_eq!(state.snakes[0].movement_direction, Direction::Up);
+        assert_eq!(state.snakes[0].head_position, Vector { x: 5, y: 4 });
+
+        state.step(None);
+        assert_eq!(state.snakes[0].movement_direction, Direction::Left);
+        assert_eq!(state.snakes[0].head_position, Vector { x: 4, y: 4 });
+        assert!(state.snakes[0].queued_directions.is_empty());
+    }
+
+    #[test]
+    fn turning_back_within_a_tick_does_not_reverse_the_snake() {
+        let mut state = empty_game(11, 2);
+
+        // Heading right, the snake is turned up and then straight back down before it moves.
+        state.queue_direction(0, Direction::Up);
+        state.queue_direction(0, Direction::Down);
+        assert_eq!(state.snakes[0].queued_directions, [Direction::Up]);
+
+        let mut events = state.step(None);
+        events.extend(state.step(None));
+
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Died { .. })));
+        assert_eq!(state.state, State::Running);
+        assert_eq!(state.snakes[0].movement_direction, Direction::Up);
+        assert_eq!(state.snakes[0].head_position, Vector { x: 5, y: 3 });
+    }
+
+    #[test]
+    fn logged_game_is_played_back_the_same_way() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("events.jsonl");
+
+        let mut state = running_game(10, 10, 5);
+        state.set_event_log(path.clone());
+        while !state.state.is_over() {
+            let input = towards_fruit(&state);
+            state.step(input);
+        }
+
+        let lines: Vec<Line> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let Some((Line::Start(start), events)) = lines.split_first() else {
+            panic!("The log does not start with the start of a game.");
+        };
+
+        // The game is played again from the seed it was logged with, turning the snake the way
+        // it was logged to have moved.
+        let mut replayed = running_game(start.width, start.height, start.seed);
+        for line in events {
+            let &Line::Event(LoggedEvent { event, .. }) = line else {
+                panic!("The log has a second game in it.");
+            };
+
+            match event {
+                GameEvent::Moved {
+                    player: 0,
+                    direction,
+                    to,
+                    ..
+                } => {
+                    replayed.step(Some(direction));
+                    assert_eq!(replayed.snakes[0].head_position, to);
+                }
+                GameEvent::Ended { outcome } => {
+                    finish(&mut replayed);
+                    assert_eq!(replayed.state, outcome);
+                }
+                _ => (),
+            }
+        }
+
+        assert!(replayed.state.is_over());
+        assert_eq!(scores(&replayed), scores(&state));
+    }
+
+    /// Fills every empty tile on the grid but `free` with walls.
+    fn fill_grid_except(state: &mut GameState, free: &[Vector]) {
+        for tile in state.tiles.iter_mut().flatten() {
+            if tile.kind == TileKind::Empty && !free.contains(&tile.position) {
+                tile.kind = TileKind::Obstacle;
+            }
+        }
+    }
+
+    /// Places a fruit, and then takes it off the grid again, returning where it was placed.
+    fn place_and_take_fruit(state: &mut GameState) -> Option<Vector> {
+        let position = state.place_fruit()?;
+        state.tiles[position.y][position.x].kind = TileKind::Empty;
+        state.fruits.clear();
+
+        Some(position)
+    }
+
+    #[test]
+    fn fruit_is_never_placed_where_the_snake_moves_next() {
+        // The snake lies across the middle row, heading right towards 3,2.
+        let mut state = empty_game(5, 2);
+        let ahead = Vector { x: 3, y: 2 };
+        let aside = Vector { x: 4, y: 4 };
+        fill_grid_except(&mut state, &[ahead, aside]);
+
+        for _ in 0..50 {
+            assert_eq!(place_and_take_fruit(&mut state), Some(aside));
+        }
+
+        // A turn queued for the next tick changes the tile the snake moves onto next.
+        let above = Vector { x: 2, y: 1 };
+        state.tiles[above.y][above.x].kind = TileKind::Empty;
+        state.tiles[aside.y][aside.x].kind = TileKind::Obstacle;
+        state.queue_direction(0, Direction::Up);
+
+        for _ in 0..50 {
+            assert_eq!(place_and_take_fruit(&mut state), Some(ahead));
+        }
+    }
+
+    #[test]
+    fn fruit_is_placed_where_the_snake_moves_next_if_nowhere_else_is_free() {
+        let mut state = empty_game(5, 2);
+        let ahead = Vector { x: 3, y: 2 };
+        fill_grid_except(&mut state, &[ahead]);
+
+        assert_eq!(state.place_fruit(), Some(ahead));
+        assert_eq!(state.fruits.len(), 1);
+    }
 }
@@ -0,0 +1,106 @@
+use crate::structs::Difficulty;
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+/// The name of the file the high scores are stored in.
+const FILE_NAME: &str = "highscore.txt";
+
+/// The path of the high score file, which is stored next to the executable.
+pub fn default_path() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|executable| {
+            executable
+                .parent()
+                .map(|directory| directory.join(FILE_NAME))
+        })
+        .unwrap_or_else(|| PathBuf::from(FILE_NAME))
+}
+
+/// Reads the high score reached on `difficulty` from the file at `path`.
+///
+/// A missing or corrupt file is treated as a high score of 0.
+pub fn load(path: &Path, difficulty: Difficulty) -> usize {
+    fs::read_to_string(path)
+        .map(|contents| parse(&contents))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(kind, _)| *kind == difficulty)
+        .map_or(0, |(_, score)| score)
+}
+
+/// Writes `score` to the file at `path` as the new high score on `difficulty`, keeping the
+/// high scores reached on the other difficulties.
+pub fn save(path: &Path, difficulty: Difficulty, score: usize) -> io::Result<()> {
+    let mut scores = fs::read_to_string(path)
+        .map(|contents| parse(&contents))
+        .unwrap_or_default();
+    scores.retain(|(kind, _)| *kind != difficulty);
+    scores.push((difficulty, score));
+
+    let lines: Vec<String> = Difficulty::ALL
+        .iter()
+        .filter_map(|difficulty| scores.iter().find(|(kind, _)| kind == difficulty))
+        .map(|(difficulty, score)| format!("{} {}", difficulty.name(), score))
+        .collect();
+
+    fs::write(path, lines.join("\n"))
+}
+
+/// Parses the high scores written by [`save`], one line for each difficulty.
+///
+/// A file holding a lone score, as written before the scores were kept apart, is taken as
+/// the high score on normal, which every game used to be played on.
+fn parse(contents: &str) -> Vec<(Difficulty, usize)> {
+    if let Ok(score) = contents.trim().parse() {
+        return vec![(Difficulty::Normal, score)];
+    }
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, score) = line.trim().split_once(' ')?;
+
+            Some((Difficulty::from_name(name)?, score.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_is_a_high_score_of_zero() {
+        let directory = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            load(&directory.path().join(FILE_NAME), Difficulty::Normal),
+            0
+        );
+    }
+
+    #[test]
+    fn corrupt_file_is_a_high_score_of_zero() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(FILE_NAME);
+        fs::write(&path, "not a high score\n\u{0}\u{1}").unwrap();
+
+        assert_eq!(load(&path, Difficulty::Normal), 0);
+    }
+
+    #[test]
+    fn saved_score_is_loaded_back_on_its_difficulty_only() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(FILE_NAME);
+
+        save(&path, Difficulty::Normal, 42).unwrap();
+        save(&path, Difficulty::Hard, 7).unwrap();
+        save(&path, Difficulty::Normal, 50).unwrap();
+
+        assert_eq!(load(&path, Difficulty::Normal), 50);
+        assert_eq!(load(&path, Difficulty::Hard), 7);
+        assert_eq!(load(&path, Difficulty::Easy), 0);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::structs::{Direction, Vector};
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Represents a level layout loaded from a map file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Level {
+    /// The dimensions of the grid.
+    pub dimensions: Vector,
+
+    /// The positions of the walls.
+    pub walls: Vec<Vector>,
+
+    /// The position the snake's head starts at.
+    pub start_position: Vector,
+
+    /// The positions of the fruits placed at the start of the game.
+    pub fruit_positions: Vec<Vector>,
+
+    /// The positions of the two ends of every portal.
+    pub portals: Vec<(Vector, Vector)>,
+}
+
+/// Reads and parses the map file at `path`.
+pub fn load(path: &Path) -> Result<Level, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read the map '{}': {}", path.display(), error))?;
+
+    parse(&source).map_err(|message| format!("Invalid map '{}': {}", path.display(), message))
+}
+
+/// Builds the built-in layout of a cross of walls through the middle of a grid of `dimensions`.
+///
+/// The snake starts in the top-left quarter, clear of both arms of the cross.
+pub fn cross(dimensions: Vector) -> Level {
+    let center = Vector {
+        x: dimensions.x / 2,
+        y: dimensions.y / 2,
+    };
+
+    let vertical_arm =
+        (dimensions.y / 4 + 1..dimensions.y - dimensions.y / 4).map(|y| Vector { x: center.x, y });
+    let horizontal_arm = (dimensions.x / 4 + 1..dimensions.x - dimensions.x / 4)
+        .filter(|&x| x != center.x)
+        .map(|x| Vector { x, y: center.y });
+
+    Level {
+        dimensions,
+        walls: vertical_arm.chain(horizontal_arm).collect(),
+        start_position: Vector {
+            x: dimensions.x / 4,
+            y: dimensions.y / 4,
+        },
+        fruit_positions: Vec::new(),
+        portals: Vec::new(),
+    }
+}
+
+/// Parses a map in which every line is a row of the grid, and every character a tile:
+///
+/// - `#` is a wall.
+/// - `.` is an empty tile.
+/// - `S` or `H` is the tile the snake's head starts on.
+/// - `F` is a tile with a fruit on it.
+/// - A digit from `1` to `9` is one end of a portal, the other end of which is the only
+///   other tile with the same digit.
+///
+/// Blank lines are ignored.
+pub fn parse(source: &str) -> Result<Level, String> {
+    let mut width = None;
+    let mut height = 0;
+    let mut walls = Vec::new();
+    let mut fruit_positions = Vec::new();
+    let mut portal_ends: Vec<(char, Vector)> = Vec::new();
+    let mut start = None;
+    let mut line_count = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim_end();
+        line_count = line_number;
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let line_width = line.chars().count();
+        let width = *width.get_or_insert(line_width);
+
+        if line_width != width {
+            return Err(format!(
+                "Line {} is {} tiles wide, but the lines before it are {} tiles wide.",
+                line_number, line_width, width
+            ));
+        }
+
+        for (x, character) in line.chars().enumerate() {
+            let position = Vector { x, y: height };
+
+            match character {
+                '#' => walls.push(position),
+                '.' => (),
+                'S' | 'H' => match start {
+                    Some((_, first_line_number)) => {
+                        return Err(format!(
+                            "Line {} has a second start cell, but there is already one on line {}.",
+                            line_number, first_line_number
+                        ))
+                    }
+                    None => start = Some((position, line_number)),
+                },
+                'F' => fruit_positions.push(position),
+                '1'..='9' => portal_ends.push((character, position)),
+                _ => {
+                    return Err(format!(
+                        "Line {} has an unknown tile '{}'.",
+                        line_number, character
+                    ))
+                }
+            }
+        }
+
+        height += 1;
+    }
+
+    let width = width.ok_or_else(|| "The map is empty.".to_owned())?;
+    let (start_position, start_line_number) = start.ok_or_else(|| {
+        format!(
+            "None of the {} lines of the map has a start cell ('S' or 'H').",
+            line_count
+        )
+    })?;
+
+    let dimensions = Vector {
+        x: width,
+        y: height,
+    };
+
+    let mut portals = Vec::new();
+
+    for digit in '1'..='9' {
+        let ends: Vec<Vector> = portal_ends
+            .iter()
+            .filter(|(character, _)| *character == digit)
+            .map(|(_, position)| *position)
+            .collect();
+
+        match ends[..] {
+            [] => (),
+            [entrance, exit] => portals.push((entrance, exit)),
+            _ => {
+                return Err(format!(
+                    "The portal '{}' has {} ends, but a portal needs exactly 2.",
+                    digit,
+                    ends.len()
+                ))
+            }
+        }
+    }
+
+    if is_enclosed(&start_position, &dimensions, &walls) {
+        return Err(format!(
+            "The start cell on line {} is enclosed by walls.",
+            start_line_number
+        ));
+    }
+
+    Ok(Level {
+        dimensions,
+        walls,
+        start_position,
+        fruit_positions,
+        portals,
+    })
+}
+
+/// Checks whether every tile next to `position` is either a wall or off the grid.
+fn is_enclosed(position: &Vector, dimensions: &Vector, walls: &[Vector]) -> bool {
+    Direction::ALL
+        .iter()
+        .filter_map(|direction| position.neighbour(*direction, dimensions))
+        .all(|neighbour| walls.contains(&neighbour))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_valid_map() {
+        let level = parse("#####\n#S.F#\n#1.1#\n#####\n").unwrap();
+
+        assert_eq!(level.dimensions, Vector { x: 5, y: 4 });
+        assert_eq!(level.start_position, Vector { x: 1, y: 1 });
+        assert_eq!(level.fruit_positions, vec![Vector { x: 3, y: 1 }]);
+        assert_eq!(
+            level.portals,
+            vec![(Vector { x: 1, y: 2 }, Vector { x: 3, y: 2 })]
+        );
+        assert_eq!(level.walls.len(), 14);
+    }
+
+    #[test]
+    fn rejects_a_map_without_a_start() {
+        let error = parse("....\n.F..\n\n....\n").unwrap_err();
+
+        assert_eq!(
+            error,
+            "None of the 4 lines of the map has a start cell ('S' or 'H')."
+        );
+    }
+
+    #[test]
+    fn rejects_a_map_with_two_starts() {
+        let error = parse("S...\n....\n..H.\n").unwrap_err();
+
+        assert_eq!(
+            error,
+            "Line 3 has a second start cell, but there is already one on line 1."
+        );
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let error = parse("....\n.S..\n...\n....\n").unwrap_err();
+
+        assert_eq!(
+            error,
+            "Line 3 is 3 tiles wide, but the lines before it are 4 tiles wide."
+        );
+    }
+
+    #[test]
+    fn rejects_an_enclosed_start() {
+        let error = parse(".....\n..#..\n.#S#.\n..#..\n").unwrap_err();
+
+        assert_eq!(error, "The start cell on line 3 is enclosed by walls.");
+    }
+
+    #[test]
+    fn rejects_a_start_enclosed_by_walls_and_the_edge_of_the_grid() {
+        let error = parse("S#..\n#...\n").unwrap_err();
+
+        assert_eq!(error, "The start cell on line 1 is enclosed by walls.");
+    }
+}
@@ -0,0 +1,16 @@
+pub mod audio;
+mod bot;
+pub mod config;
+pub mod constants;
+mod event_log;
+pub mod game;
+mod highscore;
+pub mod levels;
+pub mod preferences;
+pub mod replay;
+pub mod save;
+mod scoreboard;
+pub mod settings;
+mod stats;
+pub mod structs;
+pub mod theme;
@@ -1,19 +1,479 @@
-mod constants;
-mod game;
-mod structs;
+use snake_game::config::GameConfig;
+use snake_game::constants::*;
+use snake_game::game::GameState;
+use snake_game::settings::{self, Config};
+use snake_game::structs::{Difficulty, GameMode, PoisonEffect};
+use snake_game::theme::{self, Theme};
+use snake_game::{levels, preferences, replay, save};
 
-use game::GameState;
-use structs::Vector;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::{env, process};
 
+use ggez::conf::WindowMode;
 use ggez::{event, graphics, GameResult};
 
+/// The options the game has been started with.
+struct Options {
+    /// The settings to set the game up with.
+    config: GameConfig,
+
+    /// The path of the level map to play on instead of a generated grid.
+    level_path: Option<PathBuf>,
+
+    /// Whether to play on the built-in cross layout instead of a generated grid.
+    cross_layout: bool,
+
+    /// Whether the first player's snake is steered by the bot.
+    autopilot: bool,
+
+    /// The path of the replay to play back instead of a new game.
+    replay_path: Option<PathBuf>,
+
+    /// Whether to carry on with the saved game instead of a new one.
+    resume: bool,
+
+    /// Whether to start in fullscreen, whatever the game was closed in.
+    fullscreen: bool,
+
+    /// Whether to start with the sounds muted.
+    muted: bool,
+
+    /// Whether to hide the tiles away from the heads of the snakes.
+    fog_of_war: bool,
+
+    /// The path of the file to record everything that happens in the games played in.
+    event_log_path: Option<PathBuf>,
+}
+
 fn main() -> GameResult {
-    let builder = ggez::ContextBuilder::new("snake_game", "vxern");
-    let (context, event_loop) = builder.build()?;
+    // The settings file is read first, for the command-line arguments to override.
+    let settings = settings::load(&settings::default_path());
+    let mut options =
+        parse_options(env::args().skip(1), settings).unwrap_or_else(|message| exit(&message));
 
-    graphics::set_window_title(&context, "Snake Game");
+    // The colours set in the theme file take the place of those of the theme chosen, and of
+    // every theme switched to after it.
+    options.config.theme_overrides =
+        theme::load_overrides(&theme::default_path()).unwrap_or_else(|message| exit(&message));
+    options.config.theme = options.config.theme_overrides.apply(options.config.theme);
+
+    let fullscreen =
+        options.fullscreen || preferences::load(&preferences::default_path()).fullscreen;
+    let muted = options.muted;
+    let fog_of_war = options.fog_of_war;
+    let event_log_path = options.event_log_path.clone();
 
-    let state = GameState::new(Vector { x: 10, y: 10 })?;
+    let mut state = match &options.replay_path {
+        Some(path) => {
+            let mut replay = replay::load(path).unwrap_or_else(|message| exit(&message));
+            replay.config = replay.config.with_unrecorded_settings(&options.config);
+
+            GameState::from_replay(replay)?
+        }
+        None if options.resume => {
+            let mut saved =
+                save::load(&save::default_path()).unwrap_or_else(|message| exit(&message));
+            saved.config = saved.config.with_unrecorded_settings(&options.config);
+
+            GameState::from_save(saved).unwrap_or_else(|message| exit(&message))
+        }
+        None => new_game(options)?,
+    };
+
+    let (window_width, window_height) = state.window_size();
+
+    // The sounds are looked for next to the sources while developing, and next to the
+    // working directory otherwise.
+    let resources_path = match env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_path) => PathBuf::from(manifest_path).join("resources"),
+        Err(_) => PathBuf::from("resources"),
+    };
+
+    let builder = ggez::ContextBuilder::new("snake_game", "vxern")
+        .window_mode(
+            WindowMode::default()
+                .dimensions(window_width, window_height)
+                .min_dimensions(MIN_WINDOW_WIDTH, MIN_WINDOW_HEIGHT)
+                .resizable(true),
+        )
+        .add_resource_path(resources_path);
+    let (mut context, event_loop) = builder.build()?;
+
+    state.load_sounds(&mut context);
+    state.set_muted(muted);
+    state.set_fog_of_war(fog_of_war);
+
+    if let Some(path) = event_log_path {
+        state.set_event_log(path);
+    }
+
+    // The game starts in whichever mode it was last closed in, unless told otherwise.
+    state.set_fullscreen(&mut context, fullscreen)?;
+
+    graphics::set_window_title(&context, "Snake Game");
 
     event::run(context, event_loop, state)
 }
+
+/// Sets up a new game according to the options given.
+fn new_game(mut options: Options) -> GameResult<GameState> {
+    // The seed is settled on up front so that it can be shown to the player, who can then
+    // pass it back in to play the same board again.
+    let seed = options.config.seed.unwrap_or_else(rand::random);
+    options.config.seed = Some(seed);
+    println!("Seed: {}", seed);
+
+    let has_level = options.level_path.is_some() || options.cross_layout;
+    if has_level && options.config.player_count > 1 {
+        exit("Levels can only be played by a single player.");
+    }
+
+    // A map file brings its own layout, so the grid size and walls given are ignored.
+    let mut state = match &options.level_path {
+        Some(path) => GameState::from_level_file(path, options.config)
+            .unwrap_or_else(|error| exit(&error.to_string())),
+        None if options.cross_layout => {
+            GameState::from_level(levels::cross(options.config.dimensions()), options.config)?
+        }
+        None => GameState::new(options.config)?,
+    };
+
+    state.set_autopilot(options.autopilot);
+
+    Ok(state)
+}
+
+/// Prints an error message and exits without starting the game.
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+/// Reads the game options from the command-line arguments, starting out from `settings`.
+fn parse_options(
+    mut arguments: impl Iterator<Item = String>,
+    settings: Config,
+) -> Result<Options, String> {
+    let mut options = Options {
+        config: settings.game_config(),
+        level_path: None,
+        cross_layout: false,
+        autopilot: false,
+        replay_path: None,
+        resume: false,
+        fullscreen: false,
+        muted: settings.mute,
+        fog_of_war: false,
+        event_log_path: None,
+    };
+
+    // The mode is put together once all of its settings have been read.
+    let mut time_attack = false;
+    let mut time_limit = None;
+    let mut bonus_seconds = None;
+    let mut seconds_per_fruit = None;
+    let mut poison_effect = None;
+
+    while let Some(argument) = arguments.next() {
+        if argument == "--level" {
+            let path = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            options.level_path = Some(PathBuf::from(path));
+            continue;
+        }
+
+        if argument == "--replay" {
+            let path = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            options.replay_path = Some(PathBuf::from(path));
+            continue;
+        }
+
+        if argument == "--event-log" {
+            let path = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            options.event_log_path = Some(PathBuf::from(path));
+            continue;
+        }
+
+        if argument == "--layout" {
+            let layout = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            if layout != "cross" {
+                return Err(format!(
+                    "Unknown layout '{}'. The only built-in layout is 'cross'.",
+                    layout
+                ));
+            }
+
+            options.cross_layout = true;
+            continue;
+        }
+
+        if argument == "--seed" {
+            let value = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            let seed = value
+                .parse()
+                .map_err(|_| format!("'{}' is not a valid value for '{}'.", value, argument))?;
+
+            options.config.seed = Some(seed);
+            continue;
+        }
+
+        if argument == "--difficulty" {
+            let name = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            options.config.difficulty = Difficulty::from_name(&name).ok_or_else(|| {
+                format!(
+                    "Unknown difficulty '{}'. Choose from 'easy', 'normal' or 'hard'.",
+                    name
+                )
+            })?;
+            continue;
+        }
+
+        if argument == "--poison-effect" {
+            let name = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            poison_effect = Some(PoisonEffect::from_name(&name).ok_or_else(|| {
+                format!(
+                    "Unknown poison effect '{}'. Choose from 'deadly' or 'shrink'.",
+                    name
+                )
+            })?);
+            continue;
+        }
+
+        if argument == "--theme" {
+            let name = arguments
+                .next()
+                .ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+            options.config.theme = Theme::from_name(&name).ok_or_else(|| {
+                let names: Vec<String> = Theme::ALL
+                    .iter()
+                    .map(|theme| format!("'{}'", theme.name.to_lowercase()))
+                    .collect();
+
+                format!(
+                    "Unknown theme '{}'. Choose from {}.",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+            continue;
+        }
+
+        if argument == "--mute" {
+            options.muted = true;
+            continue;
+        }
+
+        if argument == "--fog" {
+            options.fog_of_war = true;
+            continue;
+        }
+
+        if argument == "--fullscreen" {
+            options.fullscreen = true;
+            continue;
+        }
+
+        if argument == "--resume" {
+            options.resume = true;
+            continue;
+        }
+
+        if argument == "--wrap" {
+            options.config.wrap_walls = true;
+            continue;
+        }
+
+        if argument == "--special-fruits" {
+            options.config.special_fruits = true;
+            continue;
+        }
+
+        if argument == "--bonus-fruit" {
+            options.config.bonus_fruit = true;
+            continue;
+        }
+
+        if argument == "--bot" {
+            options.autopilot = true;
+            continue;
+        }
+
+        if argument == "--time-attack" {
+            time_attack = true;
+            continue;
+        }
+
+        if argument == "--time-limit" {
+            let range = 1..=MAX_TIME_ATTACK_SECONDS;
+
+            time_limit = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--bonus-seconds" {
+            let range = 0..=MAX_TIME_ATTACK_BONUS_SECONDS;
+
+            bonus_seconds = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--timed" {
+            let range = 1..=MAX_SECONDS_PER_FRUIT;
+
+            seconds_per_fruit = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--speed" {
+            let range = MIN_MILLISECONDS_PER_FRAME..=MAX_MILLISECONDS_PER_FRAME;
+
+            options.config.milliseconds_per_frame =
+                Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--width" {
+            let range = MIN_GRID_SIZE..=MAX_GRID_SIZE;
+
+            options.config.width = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--height" {
+            let range = MIN_GRID_SIZE..=MAX_GRID_SIZE;
+
+            options.config.height = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--fruits" {
+            let range = 1..=MAX_FRUIT_COUNT;
+
+            options.config.fruit_count = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--speed-ramp" {
+            let range = 0..=MAX_SPEED_RAMP;
+
+            options.config.speed_ramp = Some(parse_value(&argument, arguments.next(), range)?);
+            continue;
+        }
+
+        if argument == "--tile-size" {
+            let range = MIN_TILE_SIZE..=MAX_TILE_SIZE;
+
+            options.config.tile_size = parse_value(&argument, arguments.next(), range)? as f32;
+            continue;
+        }
+
+        if argument == "--volume" {
+            let percentage = parse_value(&argument, arguments.next(), 0..=100)?;
+
+            options.config.volume = percentage as f32 / 100.0;
+            continue;
+        }
+
+        if argument == "--border" {
+            let range = 0..=MAX_BORDER_SIZE;
+
+            options.config.border_size = parse_value(&argument, arguments.next(), range)? as f32;
+            continue;
+        }
+
+        let (option, range) = match argument.as_str() {
+            "--walls" => (&mut options.config.obstacle_count, 0..=MAX_WALL_COUNT),
+            "--portals" => (&mut options.config.portal_count, 0..=MAX_PORTAL_COUNT),
+            "--tail" => (&mut options.config.initial_tail_length, 0..=MAX_TAIL_LENGTH),
+            "--shrink" => (&mut options.config.shrink_interval, 1..=MAX_SHRINK_INTERVAL),
+            "--poison" => (&mut options.config.poison_interval, 1..=MAX_POISON_INTERVAL),
+            "--growth" => (
+                &mut options.config.growth_per_fruit,
+                1..=MAX_GROWTH_PER_FRUIT,
+            ),
+            "--boost-cost" => (&mut options.config.boost_tail_cost, 1..=MAX_BOOST_TAIL_COST),
+            "--players" => (&mut options.config.player_count, 1..=MAX_PLAYER_COUNT),
+            "--max-combo" => (&mut options.config.max_combo, 1..=MAX_COMBO),
+            _ => return Err(format!("Unknown argument '{}'.", argument)),
+        };
+
+        *option = parse_value(&argument, arguments.next(), range)?;
+    }
+
+    if let Some(poison_effect) = poison_effect {
+        if options.config.poison_interval == 0 {
+            return Err("'--poison-effect' can only be given with '--poison'.".to_owned());
+        }
+
+        options.config.poison_effect = poison_effect;
+    }
+
+    if time_attack && seconds_per_fruit.is_some() {
+        return Err("'--time-attack' and '--timed' cannot be given together.".to_owned());
+    }
+
+    if let Some(seconds_per_fruit) = seconds_per_fruit {
+        options.config.mode = GameMode::Timed { seconds_per_fruit };
+    }
+
+    if time_attack {
+        options.config.mode = GameMode::TimeAttack {
+            seconds: time_limit.unwrap_or(DEFAULT_TIME_ATTACK_SECONDS),
+            bonus_seconds: bonus_seconds.unwrap_or(DEFAULT_TIME_ATTACK_BONUS_SECONDS),
+        };
+    } else if time_limit.is_some() || bonus_seconds.is_some() {
+        return Err(
+            "'--time-limit' and '--bonus-seconds' can only be given with '--time-attack'."
+                .to_owned(),
+        );
+    }
+
+    Ok(options)
+}
+
+/// Parses the value given to an argument, making sure it falls within `range`.
+fn parse_value(
+    argument: &str,
+    value: Option<String>,
+    range: RangeInclusive<usize>,
+) -> Result<usize, String> {
+    let value = value.ok_or_else(|| format!("Missing a value for '{}'.", argument))?;
+
+    let number = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid value for '{}'.", value, argument))?;
+
+    if !range.contains(&number) {
+        return Err(format!(
+            "The value for '{}' must be between {} and {}, but {} was given.",
+            argument,
+            range.start(),
+            range.end(),
+            number
+        ));
+    }
+
+    Ok(number)
+}
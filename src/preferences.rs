@@ -0,0 +1,45 @@
+use crate::highscore;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name of the file the preferences are stored in.
+const FILE_NAME: &str = "preferences.txt";
+
+/// Represents the choices a player has made that carry over from one session to the next.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Preferences {
+    /// Whether the game is shown in fullscreen.
+    pub fullscreen: bool,
+}
+
+/// The path of the preferences file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads the preferences from the file at `path`.
+///
+/// A missing file, or any line that cannot be made sense of, leaves the defaults in place.
+pub fn load(path: &Path) -> Preferences {
+    let mut preferences = Preferences::default();
+
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return preferences,
+    };
+
+    for line in contents.lines() {
+        if let Some(("fullscreen", value)) = line.split_once(' ') {
+            preferences.fullscreen = value.parse().unwrap_or(preferences.fullscreen);
+        }
+    }
+
+    preferences
+}
+
+/// Writes `preferences` to the file at `path`.
+pub fn save(path: &Path, preferences: &Preferences) -> io::Result<()> {
+    fs::write(path, format!("fullscreen {}", preferences.fullscreen))
+}
@@ -0,0 +1,119 @@
+use crate::config::GameConfig;
+use crate::highscore;
+use crate::structs::{Direction, State};
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the file the last saved replay is stored in.
+const FILE_NAME: &str = "last.replay";
+
+/// The version of the layout of replay files, which goes up whenever it changes.
+const VERSION: usize = 14;
+
+/// Represents a recorded game, from the settings it was set up with to the moves made in it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+    /// The settings the game was set up with, including the seed of its grid.
+    pub config: GameConfig,
+
+    /// The direction each snake moved in, tick by tick.
+    pub moves: Vec<Vec<Direction>>,
+
+    /// Whether the snakes were boosted, tick by tick.
+    pub boosts: Vec<bool>,
+
+    /// The state the game ended in.
+    pub outcome: State,
+
+    /// The scores of the players at the end of the game.
+    pub scores: Vec<usize>,
+}
+
+/// Represents a replay as it is written to its file, marked with the version of the layout
+/// it was written in.
+#[derive(Serialize)]
+struct ReplayFile<'a> {
+    version: usize,
+
+    #[serde(flatten)]
+    replay: &'a Replay,
+}
+
+/// Represents the version of the layout a replay file was written in, which is read before
+/// the rest of the file so that a file from another version is told apart from a corrupt one.
+#[derive(Deserialize)]
+struct Version {
+    version: usize,
+}
+
+/// The path of the replay file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads and parses the replay file at `path`.
+pub fn load(path: &Path) -> Result<Replay, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read the replay '{}': {}", path.display(), error))?;
+
+    parse(&source).map_err(|message| format!("Invalid replay '{}': {}", path.display(), message))
+}
+
+/// Writes `replay` to the file at `path` as JSON.
+pub fn save(path: &Path, replay: &Replay) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    serde_json::to_writer(
+        &mut file,
+        &ReplayFile {
+            version: VERSION,
+            replay,
+        },
+    )?;
+
+    file.flush()
+}
+
+/// Parses a replay in the format written by [`save`], checking that it describes a game
+/// that can be played back.
+pub fn parse(source: &str) -> Result<Replay, String> {
+    let is_current_version =
+        serde_json::from_str::<Version>(source).is_ok_and(|Version { version }| version == VERSION);
+
+    if !is_current_version {
+        return Err(
+            "The file is not a replay, or was recorded by an incompatible version of the game."
+                .to_owned(),
+        );
+    }
+
+    let replay: Replay = serde_json::from_str(source).map_err(|error| error.to_string())?;
+
+    replay.config.validate()?;
+
+    if !replay.outcome.is_over() {
+        return Err(format!(
+            "The replay ends in a game that is {}, rather than one that is over.",
+            replay.outcome
+        ));
+    }
+
+    if let Some((index, tick)) = replay
+        .moves
+        .iter()
+        .enumerate()
+        .find(|(_, tick)| tick.len() != replay.config.player_count)
+    {
+        return Err(format!(
+            "Move {} has {} directions, but there are {} players.",
+            index + 1,
+            tick.len(),
+            replay.config.player_count
+        ));
+    }
+
+    Ok(replay)
+}
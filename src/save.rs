@@ -0,0 +1,175 @@
+use crate::config::GameConfig;
+use crate::highscore;
+use crate::levels::Level;
+use crate::structs::{Fruit, PowerUp, Snake, State, Vector};
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the file the game is saved to.
+const FILE_NAME: &str = "game.save";
+
+/// The version of the layout of save files, which goes up whenever it changes.
+const VERSION: usize = 19;
+
+/// Represents a game in progress, with everything needed to carry on playing it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SavedGame {
+    /// The settings the game was set up with.
+    pub config: GameConfig,
+
+    /// The state the game was in, which is either running or counting down.
+    pub state: State,
+
+    /// The time elapsed since the last update.
+    pub ms_since_last_update: usize,
+
+    /// The time the game had been running for.
+    pub elapsed_ms: usize,
+
+    /// The time the game had been running for when a fruit was last eaten.
+    pub last_fruit_ms: usize,
+
+    /// The effect of the last power-up eaten, if it still lasts, and the number of ticks it
+    /// lasts for.
+    pub active_power_up: Option<(PowerUp, usize)>,
+
+    /// The number of fruits eaten by all the snakes together.
+    pub fruits_eaten: usize,
+
+    /// The number of ticks the game had been running for.
+    pub ticks: usize,
+
+    /// The number of tiles the snakes had moved while boosted.
+    pub boosted_tiles: usize,
+
+    /// The position of the bonus fruit, if there is one, and the number of ticks left
+    /// before it disappears.
+    pub bonus_fruit: Option<(Vector, usize)>,
+
+    /// The number of ticks left before the next bonus fruit appears.
+    pub bonus_fruit_cooldown: usize,
+
+    /// The positions of the poisoned tiles, each with the number of ticks left before it
+    /// clears up.
+    pub hazards: Vec<(Vector, usize)>,
+
+    /// The number of ticks left before the next tile is poisoned.
+    pub hazard_cooldown: usize,
+
+    /// The seed the randomness is picked up again from.
+    pub rng_seed: u64,
+
+    /// Whether the first player's snake is steered by the bot.
+    pub autopilot: bool,
+
+    /// The positions of the walls, including those the arena has closed in by.
+    pub walls: Vec<Vector>,
+
+    /// The number of rings around the edge of the grid that had been closed off with walls
+    /// as the arena shrank.
+    pub rings_closed: usize,
+
+    /// The positions of the two ends of every portal.
+    pub portals: Vec<(Vector, Vector)>,
+
+    /// The fruits on the grid.
+    pub fruits: Vec<Fruit>,
+
+    /// The level the game has been laid out from, if any.
+    pub level: Option<Level>,
+
+    /// The snakes on the grid, one for each player.
+    pub snakes: Vec<Snake>,
+}
+
+/// Represents a game as it is written to the save file, marked with the version of the
+/// layout it was written in.
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: usize,
+
+    #[serde(flatten)]
+    game: &'a SavedGame,
+}
+
+/// Represents the version of the layout a save file was written in, which is read before the
+/// rest of the file so that a file from another version is told apart from a corrupt one.
+#[derive(Deserialize)]
+struct Version {
+    version: usize,
+}
+
+/// The path of the save file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads and parses the save file at `path`.
+pub fn load(path: &Path) -> Result<SavedGame, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read the save '{}': {}", path.display(), error))?;
+
+    parse(&source).map_err(|message| format!("Invalid save '{}': {}", path.display(), message))
+}
+
+/// Writes `game` to the file at `path` as JSON.
+pub fn save(path: &Path, game: &SavedGame) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    serde_json::to_writer(
+        &mut file,
+        &SaveFile {
+            version: VERSION,
+            game,
+        },
+    )?;
+
+    file.flush()
+}
+
+/// Parses a save in the format written by [`save`].
+///
+/// Only the shape of the file is checked here; whether the parts of the game fit on the
+/// grid together is up to the game being rebuilt from it.
+pub fn parse(source: &str) -> Result<SavedGame, String> {
+    let is_current_version =
+        serde_json::from_str::<Version>(source).is_ok_and(|Version { version }| version == VERSION);
+
+    if !is_current_version {
+        return Err(
+            "The file is not a save, or was written by an incompatible version of the game."
+                .to_owned(),
+        );
+    }
+
+    let mut game: SavedGame = serde_json::from_str(source).map_err(|error| error.to_string())?;
+
+    game.config.validate()?;
+
+    if game.snakes.len() != game.config.player_count {
+        return Err(format!(
+            "The save has {} snakes, but there are {} players.",
+            game.snakes.len(),
+            game.config.player_count
+        ));
+    }
+
+    if game.last_fruit_ms > game.elapsed_ms {
+        return Err(format!(
+            "The save has the last fruit eaten {} ms into the game, but the game has only been \
+             running for {} ms.",
+            game.last_fruit_ms, game.elapsed_ms
+        ));
+    }
+
+    // The tiles the snakes lie across are not saved, being no more than a count of the
+    // positions of their parts.
+    for snake in &mut game.snakes {
+        snake.count_occupied_positions();
+    }
+
+    Ok(game)
+}
@@ -0,0 +1,155 @@
+use crate::constants::SCOREBOARD_LENGTH;
+use crate::highscore;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name of the file the scoreboard is stored in.
+const FILE_NAME: &str = "scoreboard.json";
+
+/// Represents a score that has made it onto the scoreboard.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Entry {
+    /// The initials of the player who scored it.
+    pub name: String,
+
+    /// The number of points scored.
+    pub score: usize,
+}
+
+/// Represents the best scores achieved across all single-player games, from the best down.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Scoreboard {
+    /// The scores on the board, of which there are at most [`SCOREBOARD_LENGTH`].
+    pub entries: Vec<Entry>,
+}
+
+impl Scoreboard {
+    /// Whether `score` is good enough to make it onto the board.
+    pub fn qualifies(&self, score: usize) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        self.entries.len() < SCOREBOARD_LENGTH
+            || self.entries.last().is_some_and(|entry| score > entry.score)
+    }
+
+    /// Puts `score` onto the board below the scores that are as good or better, pushing the
+    /// worst score off the board if it is full.
+    pub fn insert(&mut self, name: String, score: usize) {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(index, Entry { name, score });
+        self.entries.truncate(SCOREBOARD_LENGTH);
+    }
+}
+
+/// The path of the scoreboard file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads the scoreboard from the file at `path`.
+///
+/// A missing or corrupt file is treated as an empty board.
+pub fn load(path: &Path) -> Scoreboard {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| parse(&contents))
+        .unwrap_or_default()
+}
+
+/// Writes `scoreboard` to the file at `path` as JSON.
+pub fn save(path: &Path, scoreboard: &Scoreboard) -> io::Result<()> {
+    fs::write(path, serde_json::to_string(scoreboard)?)
+}
+
+/// Parses the JSON written by [`save`], or returns `None` if it is invalid.
+///
+/// The scores are put back onto the board one by one rather than taken as they are, so that
+/// a board edited by hand is still in order and no longer than it should be.
+fn parse(contents: &str) -> Option<Scoreboard> {
+    let entries = serde_json::from_str::<Scoreboard>(contents).ok()?.entries;
+    let mut scoreboard = Scoreboard::default();
+
+    for entry in entries {
+        scoreboard.insert(entry.name, entry.score);
+    }
+
+    Some(scoreboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(scores: &[(&str, usize)]) -> Scoreboard {
+        let mut scoreboard = Scoreboard::default();
+
+        for (name, score) in scores {
+            scoreboard.insert((*name).to_owned(), *score);
+        }
+
+        scoreboard
+    }
+
+    fn names(scoreboard: &Scoreboard) -> Vec<&str> {
+        scoreboard
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn scores_are_ordered_from_the_best_down() {
+        let scoreboard = board(&[("AAA", 5), ("BBB", 9), ("CCC", 5), ("DDD", 1), ("EEE", 7)]);
+
+        // A score goes below the scores that are as good, which were there first.
+        assert_eq!(names(&scoreboard), ["BBB", "EEE", "AAA", "CCC", "DDD"]);
+    }
+
+    #[test]
+    fn board_keeps_only_the_best_scores() {
+        let mut scoreboard = Scoreboard::default();
+        for score in 1..=SCOREBOARD_LENGTH + 3 {
+            scoreboard.insert(format!("P{}", score), score);
+        }
+
+        assert_eq!(scoreboard.entries.len(), SCOREBOARD_LENGTH);
+        assert_eq!(scoreboard.entries[0].score, SCOREBOARD_LENGTH + 3);
+        assert_eq!(scoreboard.entries[SCOREBOARD_LENGTH - 1].score, 4);
+        assert!(!scoreboard.qualifies(4));
+        assert!(scoreboard.qualifies(5));
+    }
+
+    #[test]
+    fn saved_board_is_loaded_back() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(FILE_NAME);
+        let scoreboard = board(&[("AAA", 12), ("BBB", 30)]);
+
+        save(&path, &scoreboard).unwrap();
+
+        assert_eq!(load(&path), scoreboard);
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_is_an_empty_board() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(FILE_NAME);
+
+        assert_eq!(load(&path), Scoreboard::default());
+
+        fs::write(&path, "{\"entries\": [{\"name\": \"AAA\", \"score\": -1}").unwrap();
+
+        assert_eq!(load(&path), Scoreboard::default());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::config::GameConfig;
+use crate::constants::*;
+use crate::highscore;
+use crate::structs::Difficulty;
+use crate::theme::Theme;
+
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// The name of the file the game can be set up in, instead of on the command line.
+const FILE_NAME: &str = "snake.toml";
+
+/// Represents the settings the game starts out with, as set in the settings file, before the
+/// command-line arguments are taken into account.
+///
+/// Every setting left out of the file keeps its default.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The number of tiles across the grid, or `None` for the difficulty to decide.
+    pub width: Option<usize>,
+
+    /// The number of tiles down the grid, or `None` for the difficulty to decide.
+    pub height: Option<usize>,
+
+    /// The time that passes between two movements of the snakes at the start of the game,
+    /// or `None` for the difficulty to decide.
+    pub speed: Option<usize>,
+
+    /// How much shorter the time between two movements gets with every fruit eaten, or
+    /// `None` for the difficulty to decide.
+    pub speed_ramp: Option<usize>,
+
+    /// Whether the snakes wrap around to the opposite edge instead of crashing into it.
+    pub wrap: bool,
+
+    /// How hard the game is.
+    pub difficulty: Difficulty,
+
+    /// The name of the palette the game is drawn in, or `None` for the default one.
+    pub theme: Option<String>,
+
+    /// Whether to start with the sounds muted.
+    pub mute: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            speed: None,
+            speed_ramp: None,
+            wrap: false,
+            difficulty: Difficulty::Normal,
+            theme: None,
+            mute: false,
+        }
+    }
+}
+
+impl Config {
+    /// The settings to set the game up with.
+    pub fn game_config(&self) -> GameConfig {
+        GameConfig {
+            width: self.width,
+            height: self.height,
+            milliseconds_per_frame: self.speed,
+            speed_ramp: self.speed_ramp,
+            wrap_walls: self.wrap,
+            difficulty: self.difficulty,
+            theme: self
+                .theme
+                .as_deref()
+                .and_then(Theme::from_name)
+                .unwrap_or_default(),
+            ..GameConfig::default()
+        }
+    }
+
+    /// Checks that every setting has a value the game can be set up with.
+    fn validate(&self) -> Result<(), String> {
+        let sizes = MIN_GRID_SIZE..=MAX_GRID_SIZE;
+        if let Some(width) = self.width {
+            check_range("width", width, sizes.clone())?;
+        }
+
+        if let Some(height) = self.height {
+            check_range("height", height, sizes)?;
+        }
+
+        if let Some(speed) = self.speed {
+            let range = MIN_MILLISECONDS_PER_FRAME..=MAX_MILLISECONDS_PER_FRAME;
+            check_range("speed", speed, range)?;
+        }
+
+        if let Some(speed_ramp) = self.speed_ramp {
+            check_range("speed_ramp", speed_ramp, 0..=MAX_SPEED_RAMP)?;
+        }
+
+        if let Some(name) = &self.theme {
+            if Theme::from_name(name).is_none() {
+                let names: Vec<String> = Theme::ALL
+                    .iter()
+                    .map(|theme| format!("'{}'", theme.name.to_lowercase()))
+                    .collect();
+
+                return Err(format!(
+                    "Unknown theme '{}'. Choose from {}.",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The path of the settings file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads the settings file at `path`, which is written in TOML, such as `width = 20`,
+/// `wrap = true` or `difficulty = "hard"`.
+///
+/// A missing file leaves every setting at its default, as does a file that cannot be read or
+/// is invalid, which is reported.
+pub fn load(path: &Path) -> Config {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Config::default(),
+        Err(error) => {
+            eprintln!("Failed to read '{}': {}", path.display(), error);
+            return Config::default();
+        }
+    };
+
+    parse(&contents).unwrap_or_else(|message| {
+        eprintln!("Invalid settings file '{}': {}", path.display(), message);
+        Config::default()
+    })
+}
+
+/// Parses the TOML of a settings file, checking that every setting has a valid value.
+fn parse(contents: &str) -> Result<Config, String> {
+    let config: Config = toml::from_str(contents).map_err(|error| error.to_string())?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+/// Checks that the value of the setting called `name` falls within `range`.
+fn check_range(name: &str, value: usize, range: RangeInclusive<usize>) -> Result<(), String> {
+    if !range.contains(&value) {
+        return Err(format!(
+            "The value for '{}' must be between {} and {}, but {} was given.",
+            name,
+            range.start(),
+            range.end(),
+            value
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_left_out_keep_their_defaults() {
+        let config = parse(
+            "# Bigger and harder # than usual\nwidth = 30\ndifficulty = \"hard\" # Not 'normal'\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                width: Some(30),
+                difficulty: Difficulty::Hard,
+                ..Config::default()
+            }
+        );
+
+        let game_config = config.game_config();
+        assert_eq!(game_config.dimensions().x, 30);
+        assert_eq!(game_config.dimensions().y, Difficulty::Hard.grid_size());
+        assert_eq!(game_config.milliseconds_per_frame, None);
+        assert_eq!(game_config.theme, Theme::default());
+    }
+
+    #[test]
+    fn empty_or_missing_file_is_the_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+
+        let directory = tempfile::tempdir().unwrap();
+        assert_eq!(load(&directory.path().join(FILE_NAME)), Config::default());
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        assert!(parse("width = 2").is_err());
+        assert!(parse("speed = 5000").is_err());
+        assert!(parse("difficulty = \"extreme\"").is_err());
+        assert!(parse("theme = \"neon\"").is_err());
+        assert!(parse("colour = \"green\"").is_err());
+        assert!(parse("wrap = \"yes\"").is_err());
+    }
+}
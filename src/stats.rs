@@ -0,0 +1,68 @@
+use crate::highscore;
+use crate::structs::Difficulty;
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the file the statistics of finished games are stored in.
+const FILE_NAME: &str = "stats.csv";
+
+/// The first line of the statistics file, naming its columns.
+const HEADER: &str = "difficulty,score,length,fruits,ticks,seconds,turns";
+
+/// Represents how a single-player game went, from the moment it started to the moment it
+/// ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+    /// The difficulty the game was played on.
+    pub difficulty: Difficulty,
+
+    /// The number of points scored.
+    pub score: usize,
+
+    /// The length of the snake, counting its head, when the game ended.
+    pub length: usize,
+
+    /// The number of fruits eaten.
+    pub fruits_eaten: usize,
+
+    /// The number of ticks the snake survived for.
+    pub ticks: usize,
+
+    /// The time the game was running for, leaving out the countdown and the time spent
+    /// paused.
+    pub elapsed_ms: usize,
+
+    /// The number of times the snake changed direction.
+    pub turns: usize,
+}
+
+/// The path of the statistics file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Adds `record` to the end of the file at `path` as a new line, creating the file with a
+/// line naming the columns first if it does not exist yet.
+pub fn append(path: &Path, record: &Record) -> io::Result<()> {
+    let is_new = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if is_new {
+        writeln!(file, "{}", HEADER)?;
+    }
+
+    writeln!(
+        file,
+        "{},{},{},{},{},{},{}",
+        record.difficulty.name(),
+        record.score,
+        record.length,
+        record.fruits_eaten,
+        record.ticks,
+        record.elapsed_ms / 1000,
+        record.turns
+    )
+}
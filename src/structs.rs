@@ -1,12 +1,113 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub};
+
+use ggez::event::GamepadId;
+use serde::{Deserialize, Serialize};
+
 /// Represents a vector value.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// Vectors are ordered by their `x` component first, and by their `y` component where the
+/// `x` components are the same.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Vector {
     pub x: usize,
     pub y: usize,
 }
 
+impl Vector {
+    /// Computes the position one tile away in the given direction, or nothing if that would
+    /// be off a grid of the given dimensions.
+    pub fn neighbour(&self, direction: Direction, dimensions: &Vector) -> Option<Vector> {
+        self.checked_offset(Offset::from_direction(direction), dimensions)
+    }
+
+    /// Computes the position `offset` away, or nothing if that would be off a grid of the
+    /// given dimensions.
+    pub fn checked_offset(&self, offset: Offset, dimensions: &Vector) -> Option<Vector> {
+        let position = Vector {
+            x: self.x.checked_add_signed(offset.x)?,
+            y: self.y.checked_add_signed(offset.y)?,
+        };
+
+        (position.x < dimensions.x && position.y < dimensions.y).then_some(position)
+    }
+
+    /// Computes the position `offset` away, wrapping around to the opposite edge of a grid of
+    /// the given dimensions.
+    pub fn wrapping_offset(&self, offset: Offset, dimensions: &Vector) -> Vector {
+        let wrap = |component: usize, offset: isize, length: usize| {
+            (component as isize + offset).rem_euclid(length as isize) as usize
+        };
+
+        Vector {
+            x: wrap(self.x, offset.x, dimensions.x),
+            y: wrap(self.y, offset.y, dimensions.y),
+        }
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl AddAssign for Vector {
+    fn add_assign(&mut self, other: Vector) {
+        *self = *self + other;
+    }
+}
+
+/// The difference between two positions is the signed offset that leads from `other` to
+/// `self`, which can go either way along each axis.
+///
+/// Both positions must lie on a grid no wider or higher than `isize::MAX` tiles, which every
+/// grid does, bounded as they are by `MAX_GRID_SIZE`.
+impl Sub for Vector {
+    type Output = Offset;
+
+    fn sub(self, other: Vector) -> Offset {
+        Offset {
+            x: self.x as isize - other.x as isize,
+            y: self.y as isize - other.y as isize,
+        }
+    }
+}
+
+/// Represents a signed distance between two positions on the grid.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Offset {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Offset {
+    /// The distance of a single step in the given direction.
+    pub fn from_direction(direction: Direction) -> Offset {
+        match direction {
+            Direction::Up => Offset { x: 0, y: -1 },
+            Direction::Down => Offset { x: 0, y: 1 },
+            Direction::Right => Offset { x: 1, y: 0 },
+            Direction::Left => Offset { x: -1, y: 0 },
+        }
+    }
+}
+
+impl fmt::Display for Vector {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "({}, {})", self.x, self.y)
+    }
+}
+
 /// Represents a direction of movement.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     /// Upwards.
     Up,
@@ -21,25 +122,606 @@ pub enum Direction {
     Left,
 }
 
+impl Direction {
+    /// Every direction of movement.
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Right,
+        Direction::Left,
+    ];
+
+    /// The direction that reverses this one.
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    /// Checks whether turning from `other` to this direction would reverse the snake.
+    pub fn is_opposite_of(&self, other: Direction) -> bool {
+        self.opposite() == other
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Right => "right",
+            Direction::Left => "left",
+        })
+    }
+}
+
 /// Represents the state of the current game.
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum State {
+    /// The game is waiting in the start menu for the players to choose the difficulty.
+    Menu,
+
+    /// The game is counting down the given number of seconds before starting.
+    Countdown(u8),
+
     /// The game is in progress.
     Running,
 
+    /// The snakes that crashed are blinking for the given number of frames before the game
+    /// ends.
+    Dying(u8),
+
     /// The game has been won.
     Won,
 
     /// The game has been lost.
     Lost,
+
+    /// The game has ended with no player winning over the others.
+    Draw,
+
+    /// The time given to a game of time attack has run out.
+    TimeUp,
+}
+
+impl State {
+    /// Whether the game has ended, one way or another.
+    pub fn is_over(&self) -> bool {
+        matches!(self, State::Won | State::Lost | State::Draw | State::TimeUp)
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            State::Menu => formatter.write_str("in the menu"),
+            State::Countdown(count) => write!(formatter, "starting in {}", count),
+            State::Running => formatter.write_str("in progress"),
+            State::Dying(_) => formatter.write_str("ending"),
+            State::Won => formatter.write_str("won"),
+            State::Lost => formatter.write_str("lost"),
+            State::Draw => formatter.write_str("a draw"),
+            State::TimeUp => formatter.write_str("out of time"),
+        }
+    }
 }
 
 /// Represents a tile on the grid.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Tile {
     /// The position of the tile.
     pub position: Vector,
 
-    /// Whether the tile is occupied by something.
-    pub is_occupied: bool,
+    /// What the tile is taken up by.
+    pub kind: TileKind,
+}
+
+/// Represents what a tile on the grid is taken up by.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum TileKind {
+    /// Nothing is on the tile.
+    Empty,
+
+    /// A part of a snake is on the tile.
+    Occupied,
+
+    /// A fruit is on the tile.
+    Fruit,
+
+    /// The tile is an obstacle, which kills the snake on contact.
+    Obstacle,
+
+    /// The tile is one end of a portal, which sends the snake out of the other end.
+    Portal,
+
+    /// The tile is poisoned for a while, which harms the snake on contact.
+    Poison,
+}
+
+/// Represents the rules a game is played by.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum GameMode {
+    /// The game goes on until the snakes crash or fill the grid.
+    Classic,
+
+    /// The game ends once the given number of seconds have passed, with every fruit eaten
+    /// adding the given number of bonus seconds.
+    TimeAttack {
+        seconds: usize,
+        bonus_seconds: usize,
+    },
+
+    /// The game is lost once the given number of seconds have passed without a fruit being
+    /// eaten.
+    Timed { seconds_per_fruit: usize },
+}
+
+/// Represents how hard the game is, which decides how fast the snakes move, how big the grid
+/// is and how many fruits are kept on it.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// The snakes move slowly, never speed up, and wrap around the edges of a bigger grid
+    /// with several fruits on it.
+    Easy,
+
+    /// The game as it was first made, with the snakes moving at a steady pace.
+    Normal,
+
+    /// The snakes move fast and speed up quickly with every fruit eaten, on a smaller grid.
+    Hard,
+}
+
+impl Difficulty {
+    /// Every difficulty, from the easiest to the hardest.
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// The name the difficulty is chosen by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// The difficulty chosen by `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.name() == name)
+    }
+
+    /// The time that passes between two movements of the snakes at the start of the game.
+    pub fn initial_milliseconds_per_frame(&self) -> usize {
+        match self {
+            Difficulty::Easy => 400,
+            Difficulty::Normal => 300,
+            Difficulty::Hard => 200,
+        }
+    }
+
+    /// How much shorter the time between two movements gets with every fruit eaten.
+    pub fn speed_ramp(&self) -> usize {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 0,
+            Difficulty::Hard => 10,
+        }
+    }
+
+    /// The number of tiles across and down the grid.
+    pub fn grid_size(&self) -> usize {
+        match self {
+            Difficulty::Easy => 12,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 8,
+        }
+    }
+
+    /// The number of fruits kept on the grid.
+    pub fn fruit_count(&self) -> usize {
+        match self {
+            Difficulty::Easy => 3,
+            Difficulty::Normal | Difficulty::Hard => 1,
+        }
+    }
+
+    /// Whether the snakes wrap around to the opposite edge instead of crashing into it.
+    pub fn wraps_walls(&self) -> bool {
+        matches!(self, Difficulty::Easy)
+    }
+}
+
+/// Represents the kind of a fruit, which decides what eating it does.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum FruitKind {
+    /// Grows the snake and is worth a single point.
+    Normal,
+
+    /// Grows the snake and is worth several points.
+    Golden,
+
+    /// Shortens the snake's tail.
+    Shrink,
+
+    /// Grows the snake and slows it down for a while.
+    Slow,
+
+    /// Grows the snake and speeds it up for a while.
+    Boost,
+
+    /// Grows the snake and keeps it from crashing for a while.
+    Shield,
+}
+
+/// Represents an effect a fruit has on the snakes for a number of ticks after being eaten.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PowerUp {
+    /// The snakes move more slowly.
+    Slowdown,
+
+    /// The snakes move faster.
+    SpeedBoost,
+
+    /// The snake stops short of whatever it would crash into, waiting for a turn, instead of
+    /// crashing.
+    Shield,
+}
+
+impl PowerUp {
+    /// The name the power-up is shown as on the HUD.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUp::Slowdown => "slow",
+            PowerUp::SpeedBoost => "boost",
+            PowerUp::Shield => "shield",
+        }
+    }
+}
+
+/// Represents what happens to a snake that runs into a poisoned tile.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PoisonEffect {
+    /// The snake dies, as it would crashing into a wall.
+    Deadly,
+
+    /// The snake loses some of its tail, using the poison up.
+    Shrink,
+}
+
+impl PoisonEffect {
+    /// Every effect poison can have.
+    pub const ALL: [PoisonEffect; 2] = [PoisonEffect::Deadly, PoisonEffect::Shrink];
+
+    /// The name the effect is given as on the command line and written down as in saves.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PoisonEffect::Deadly => "deadly",
+            PoisonEffect::Shrink => "shrink",
+        }
+    }
+
+    /// The effect written down as `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<PoisonEffect> {
+        PoisonEffect::ALL
+            .iter()
+            .copied()
+            .find(|effect| effect.name() == name)
+    }
+}
+
+/// Represents something that happened over the course of a tick, for the game to be
+/// followed by without watching its state.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// The snake of the given player moved its head from one tile onto another, heading in
+    /// the given direction.
+    Moved {
+        player: usize,
+        direction: Direction,
+        from: Vector,
+        to: Vector,
+    },
+
+    /// The snake of the given player ate the fruit at the given position.
+    AteFruit { player: usize, position: Vector },
+
+    /// A fruit was put on the grid at the given position.
+    FruitPlaced { position: Vector },
+
+    /// The bonus fruit at the given position disappeared without being eaten.
+    SpecialFruitExpired { position: Vector },
+
+    /// The snake of the given player crashed.
+    Died { player: usize, reason: DeathReason },
+
+    /// The game came to an end, with the given outcome.
+    Ended { outcome: State },
+}
+
+/// Represents what a snake crashed into.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathReason {
+    /// The snake ran off the edge of the grid.
+    HitWall,
+
+    /// The snake ran into its own tail.
+    HitSelf,
+
+    /// The snake ran into another snake.
+    HitSnake,
+
+    /// The snake met another snake head-on.
+    HeadOn,
+
+    /// The snake ran into one of the walls on the grid.
+    HitObstacle,
+
+    /// The snake ran into a poisoned tile.
+    Poisoned,
+}
+
+/// Represents a fruit on the grid.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fruit {
+    /// The position of the fruit.
+    pub position: Vector,
+
+    /// The kind of the fruit.
+    pub kind: FruitKind,
+}
+
+/// Represents a snake controlled by a player.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Snake {
+    /// The position of the snake's head.
+    pub head_position: Vector,
+
+    /// The positions of the snake's tail parts.
+    pub tail_positions: VecDeque<Vector>,
+
+    /// The number of the snake's parts, counting its head, on each of the tiles it lies
+    /// across, for telling whether it lies across a tile without going along the whole of it.
+    ///
+    /// The parts are counted rather than just noted down, as a snake in ghost mode can pass
+    /// over itself.
+    ///
+    /// The counts are left out of saves, and counted again from the positions once read.
+    #[serde(skip)]
+    pub(crate) occupied_positions: HashMap<Vector, usize>,
+
+    /// The direction of movement of the snake.
+    pub movement_direction: Direction,
+
+    /// The directions the snake will turn in over the next ticks, one per tick.
+    pub queued_directions: VecDeque<Direction>,
+
+    /// The number of ticks for which the tail will keep growing.
+    pub pending_growth: usize,
+
+    /// The number of points scored by eating fruits.
+    pub score: usize,
+
+    /// The number of points scored by eating fruits, before they were multiplied by combos.
+    pub base_score: usize,
+
+    /// The multiplier the points of the last fruit eaten were scored with, which goes up with
+    /// every fruit eaten in quick succession.
+    pub combo: usize,
+
+    /// The number of ticks since the last fruit was eaten, or `None` if the combo has run out.
+    pub ticks_since_fruit: Option<usize>,
+
+    /// The highest multiplier reached.
+    pub best_combo: usize,
+
+    /// The number of times the snake has changed direction.
+    pub turns: usize,
+
+    /// The positions of the head and the tail parts before the snake last moved, which the
+    /// snake is drawn sliding away from.
+    pub previous_positions: Vec<Vector>,
+}
+
+impl Snake {
+    /// The positions of the head and the tail parts, from the head down to the tip.
+    pub fn positions(&self) -> impl Iterator<Item = Vector> + '_ {
+        std::iter::once(self.head_position).chain(self.tail_positions.iter().copied())
+    }
+
+    /// Whether any part of the snake lies on `position`.
+    pub fn occupies(&self, position: Vector) -> bool {
+        self.occupied_positions.contains_key(&position)
+    }
+
+    /// Counts the parts on each of the tiles the snake lies across from scratch.
+    pub(crate) fn count_occupied_positions(&mut self) {
+        let mut occupied_positions = HashMap::new();
+
+        for position in self.positions() {
+            *occupied_positions.entry(position).or_default() += 1;
+        }
+
+        self.occupied_positions = occupied_positions;
+    }
+
+    /// Moves the head onto `position`, leaving a new tail part where it was.
+    pub fn move_head(&mut self, position: Vector) {
+        let previous_position = self.head_position;
+        self.head_position = position;
+        self.tail_positions.push_front(previous_position);
+
+        *self.occupied_positions.entry(position).or_default() += 1;
+    }
+
+    /// Adds a part to the end of the tail, on `position`.
+    pub fn push_tail(&mut self, position: Vector) {
+        self.tail_positions.push_back(position);
+
+        *self.occupied_positions.entry(position).or_default() += 1;
+    }
+
+    /// Takes the last part off the end of the tail, returning where it was, if the snake has
+    /// a tail.
+    pub fn pop_tail(&mut self) -> Option<Vector> {
+        let position = self.tail_positions.pop_back()?;
+
+        if let Some(count) = self.occupied_positions.get_mut(&position) {
+            *count -= 1;
+
+            if *count == 0 {
+                self.occupied_positions.remove(&position);
+            }
+        }
+
+        Some(position)
+    }
+
+    /// The direction the snake will move in on the next tick, which is the first queued turn
+    /// if there is one.
+    pub fn next_direction(&self) -> Direction {
+        self.queued_directions
+            .front()
+            .copied()
+            .unwrap_or(self.movement_direction)
+    }
+
+    pub fn new(head_position: Vector, movement_direction: Direction) -> Self {
+        Self {
+            head_position,
+            tail_positions: VecDeque::new(),
+            occupied_positions: HashMap::from([(head_position, 1)]),
+            movement_direction,
+            queued_directions: VecDeque::new(),
+            pending_growth: 0,
+            score: 0,
+            base_score: 0,
+            combo: 1,
+            ticks_since_fruit: None,
+            best_combo: 1,
+            turns: 0,
+            previous_positions: Vec::new(),
+        }
+    }
+}
+
+/// Represents where the grid is drawn on the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    /// The length of the sides of a tile.
+    pub tile_size: f32,
+
+    /// The position of the top-left corner of the grid.
+    pub origin: (f32, f32),
+}
+
+/// Represents a gamepad steering the snake of the player it has been assigned to.
+#[derive(Clone, Copy, Debug)]
+pub struct Gamepad {
+    /// The identifier of the gamepad.
+    pub id: GamepadId,
+
+    /// The position of the left stick, from -1 to 1 along each axis, with up being positive.
+    pub stick_position: (f32, f32),
+
+    /// The direction the left stick is pushed in, if it is pushed past the deadzone.
+    pub stick_direction: Option<Direction>,
+}
+
+impl Gamepad {
+    pub fn new(id: GamepadId) -> Self {
+        Self {
+            id,
+            stick_position: (0.0, 0.0),
+            stick_direction: None,
+        }
+    }
+}
+
+/// Represents something the players can ask of the game, whether from the keyboard or a
+/// gamepad.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Turns the snake of the given player in the given direction.
+    Turn(usize, Direction),
+
+    /// Starts the game from the menu.
+    Start,
+
+    /// Pauses the game, or carries on with it if it is paused.
+    TogglePause,
+
+    /// Starts a new game once the current one is over.
+    Restart,
+
+    /// Writes the game in progress to the save file.
+    Save,
+
+    /// Picks up the game from the save file.
+    Load,
+
+    /// Plays back the last game recorded.
+    WatchReplay,
+
+    /// Shows the scoreboard once the game is over, or hides it again.
+    ToggleScoreboard,
+
+    /// Switches the window in or out of fullscreen.
+    ToggleFullscreen,
+
+    /// Switches between drawing the grid in full detail and as an overview of flat blocks.
+    ToggleOverview,
+
+    /// Hands the first player's snake over to the bot, or takes it back.
+    ToggleAutopilot,
+
+    /// Lets the snakes pass through walls and tails without crashing, or stops letting them.
+    ToggleGhostMode,
+
+    /// Silences the sounds, or brings them back.
+    ToggleMute,
+
+    /// Switches to the next theme.
+    NextTheme,
+
+    /// Speeds the snakes up while the boost key is held, or lets them slow down again once
+    /// it is let go of.
+    Boost(bool),
+
+    /// Closes the game.
+    Quit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difference_of_positions_is_a_signed_offset() {
+        let a = Vector { x: 2, y: 7 };
+        let b = Vector { x: 5, y: 3 };
+
+        assert_eq!(a - b, Offset { x: -3, y: 4 });
+        assert_eq!(b - a, Offset { x: 3, y: -4 });
+        assert_eq!(a - a, Offset { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn offsetting_by_a_difference_leads_back() {
+        let dimensions = Vector { x: 10, y: 10 };
+        let a = Vector { x: 2, y: 7 };
+        let b = Vector { x: 5, y: 3 };
+
+        assert_eq!(b.checked_offset(a - b, &dimensions), Some(a));
+        assert_eq!(a.checked_offset(a - b, &dimensions), None);
+        assert_eq!(a.wrapping_offset(a - b, &dimensions), Vector { x: 9, y: 1 });
+    }
 }
@@ -0,0 +1,436 @@
+use crate::highscore;
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use ggez::graphics::Color;
+use serde::Deserialize;
+
+/// The name of the file the colours of the theme can be overridden in.
+const FILE_NAME: &str = "theme.toml";
+
+/// Represents a palette the board and the first player's snake are drawn in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    /// The name the theme is known by.
+    pub name: &'static str,
+
+    /// The colour behind the grid.
+    pub background: Color,
+
+    /// The lighter of the two colours the tiles of the grid alternate between.
+    pub tile_light: Color,
+
+    /// The darker of the two colours the tiles of the grid alternate between.
+    pub tile_dark: Color,
+
+    /// The colour of the snake's head.
+    pub head: Color,
+
+    /// The colour of the tail piece right behind the head, which fades into the colour of
+    /// the tip along the tail.
+    pub tail_light: Color,
+
+    /// The colour of the tip of the tail.
+    pub tail_dark: Color,
+
+    /// The colour of the ordinary fruit.
+    pub fruit: Color,
+}
+
+/// The green snake on a dark grey board the game has always been drawn in.
+pub const THEME_DARK: Theme = Theme {
+    name: "Dark",
+    background: Color {
+        r: 41.0 / 255.0,
+        g: 41.0 / 255.0,
+        b: 41.0 / 255.0,
+        a: 1.0,
+    },
+    tile_light: Color {
+        r: 59.0 / 255.0,
+        g: 59.0 / 255.0,
+        b: 59.0 / 255.0,
+        a: 1.0,
+    },
+    tile_dark: Color {
+        r: 51.0 / 255.0,
+        g: 51.0 / 255.0,
+        b: 51.0 / 255.0,
+        a: 1.0,
+    },
+    head: Color {
+        r: 19.0 / 255.0,
+        g: 138.0 / 255.0,
+        b: 54.0 / 255.0,
+        a: 1.0,
+    },
+    tail_light: Color {
+        r: 12.0 / 255.0,
+        g: 185.0 / 255.0,
+        b: 45.0 / 255.0,
+        a: 1.0,
+    },
+    tail_dark: Color {
+        r: 10.0 / 255.0,
+        g: 80.0 / 255.0,
+        b: 30.0 / 255.0,
+        a: 1.0,
+    },
+    fruit: Color {
+        r: 1.0,
+        g: 87.0 / 255.0,
+        b: 51.0 / 255.0,
+        a: 1.0,
+    },
+};
+
+/// A green snake on a black board, after the original game on monochrome phone screens.
+pub const THEME_CLASSIC: Theme = Theme {
+    name: "Classic",
+    background: Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    },
+    tile_light: Color {
+        r: 14.0 / 255.0,
+        g: 18.0 / 255.0,
+        b: 14.0 / 255.0,
+        a: 1.0,
+    },
+    tile_dark: Color {
+        r: 6.0 / 255.0,
+        g: 8.0 / 255.0,
+        b: 6.0 / 255.0,
+        a: 1.0,
+    },
+    head: Color {
+        r: 120.0 / 255.0,
+        g: 1.0,
+        b: 80.0 / 255.0,
+        a: 1.0,
+    },
+    tail_light: Color {
+        r: 70.0 / 255.0,
+        g: 220.0 / 255.0,
+        b: 50.0 / 255.0,
+        a: 1.0,
+    },
+    tail_dark: Color {
+        r: 20.0 / 255.0,
+        g: 110.0 / 255.0,
+        b: 20.0 / 255.0,
+        a: 1.0,
+    },
+    fruit: Color {
+        r: 200.0 / 255.0,
+        g: 1.0,
+        b: 160.0 / 255.0,
+        a: 1.0,
+    },
+};
+
+/// Soft pastel colours on a light board.
+pub const THEME_LIGHT: Theme = Theme {
+    name: "Light",
+    background: Color {
+        r: 236.0 / 255.0,
+        g: 230.0 / 255.0,
+        b: 218.0 / 255.0,
+        a: 1.0,
+    },
+    tile_light: Color {
+        r: 250.0 / 255.0,
+        g: 246.0 / 255.0,
+        b: 238.0 / 255.0,
+        a: 1.0,
+    },
+    tile_dark: Color {
+        r: 241.0 / 255.0,
+        g: 235.0 / 255.0,
+        b: 224.0 / 255.0,
+        a: 1.0,
+    },
+    head: Color {
+        r: 86.0 / 255.0,
+        g: 150.0 / 255.0,
+        b: 112.0 / 255.0,
+        a: 1.0,
+    },
+    tail_light: Color {
+        r: 132.0 / 255.0,
+        g: 196.0 / 255.0,
+        b: 152.0 / 255.0,
+        a: 1.0,
+    },
+    tail_dark: Color {
+        r: 72.0 / 255.0,
+        g: 118.0 / 255.0,
+        b: 92.0 / 255.0,
+        a: 1.0,
+    },
+    fruit: Color {
+        r: 232.0 / 255.0,
+        g: 112.0 / 255.0,
+        b: 104.0 / 255.0,
+        a: 1.0,
+    },
+};
+
+/// An amber snake on a near-black board, after old monochrome monitors.
+pub const THEME_RETRO: Theme = Theme {
+    name: "Retro",
+    background: Color {
+        r: 18.0 / 255.0,
+        g: 12.0 / 255.0,
+        b: 4.0 / 255.0,
+        a: 1.0,
+    },
+    tile_light: Color {
+        r: 37.0 / 255.0,
+        g: 26.0 / 255.0,
+        b: 10.0 / 255.0,
+        a: 1.0,
+    },
+    tile_dark: Color {
+        r: 30.0 / 255.0,
+        g: 21.0 / 255.0,
+        b: 8.0 / 255.0,
+        a: 1.0,
+    },
+    head: Color {
+        r: 1.0,
+        g: 176.0 / 255.0,
+        b: 0.0,
+        a: 1.0,
+    },
+    tail_light: Color {
+        r: 1.0,
+        g: 200.0 / 255.0,
+        b: 70.0 / 255.0,
+        a: 1.0,
+    },
+    tail_dark: Color {
+        r: 130.0 / 255.0,
+        g: 80.0 / 255.0,
+        b: 0.0,
+        a: 1.0,
+    },
+    fruit: Color {
+        r: 1.0,
+        g: 236.0 / 255.0,
+        b: 170.0 / 255.0,
+        a: 1.0,
+    },
+};
+
+/// A blue snake and yellow fruit on a near-black board, for players who cannot tell green
+/// and red apart.
+///
+/// The blue and the yellow come from the Okabe-Ito palette, which stays distinct under the
+/// common kinds of colour blindness. Against the lighter and the darker tiles, by the WCAG
+/// definition of contrast ratio, the colours stand out by:
+///
+/// - head (86, 180, 233): 6.9:1 and 7.7:1,
+/// - tail behind the head (140, 210, 250): 9.6:1 and 10.8:1,
+/// - tip of the tail (20, 130, 200): 3.8:1 and 4.3:1,
+/// - fruit (240, 228, 66): 12.0:1 and 13.4:1,
+///
+/// all above the 3:1 WCAG asks of graphical objects.
+pub const THEME_COLORBLIND: Theme = Theme {
+    name: "Colorblind",
+    background: Color {
+        r: 12.0 / 255.0,
+        g: 12.0 / 255.0,
+        b: 12.0 / 255.0,
+        a: 1.0,
+    },
+    tile_light: Color {
+        r: 34.0 / 255.0,
+        g: 34.0 / 255.0,
+        b: 34.0 / 255.0,
+        a: 1.0,
+    },
+    tile_dark: Color {
+        r: 24.0 / 255.0,
+        g: 24.0 / 255.0,
+        b: 24.0 / 255.0,
+        a: 1.0,
+    },
+    head: Color {
+        r: 86.0 / 255.0,
+        g: 180.0 / 255.0,
+        b: 233.0 / 255.0,
+        a: 1.0,
+    },
+    tail_light: Color {
+        r: 140.0 / 255.0,
+        g: 210.0 / 255.0,
+        b: 250.0 / 255.0,
+        a: 1.0,
+    },
+    tail_dark: Color {
+        r: 20.0 / 255.0,
+        g: 130.0 / 255.0,
+        b: 200.0 / 255.0,
+        a: 1.0,
+    },
+    fruit: Color {
+        r: 240.0 / 255.0,
+        g: 228.0 / 255.0,
+        b: 66.0 / 255.0,
+        a: 1.0,
+    },
+};
+
+impl Theme {
+    /// Every theme, in the order they are switched through.
+    pub const ALL: [Theme; 5] = [
+        THEME_DARK,
+        THEME_CLASSIC,
+        THEME_LIGHT,
+        THEME_RETRO,
+        THEME_COLORBLIND,
+    ];
+
+    /// The theme called `name`, whatever its case, if there is one.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::ALL
+            .iter()
+            .copied()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The theme that comes after this one when switching through them.
+    pub fn next(&self) -> Theme {
+        let index = Theme::ALL
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| index + 1);
+
+        Theme::ALL[index % Theme::ALL.len()]
+    }
+}
+
+/// The path of the theme file, which is stored next to the high score file.
+pub fn default_path() -> PathBuf {
+    highscore::default_path().with_file_name(FILE_NAME)
+}
+
+/// Reads the colours set in the theme file at `path`.
+///
+/// Every colour is set as an array of its red, green, blue and alpha values, each between 0
+/// and 255, such as `head_color = [19, 138, 54, 255]`. A missing file sets no colours.
+pub fn load_overrides(path: &Path) -> Result<ThemeOverrides, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(ThemeOverrides::default()),
+        Err(error) => return Err(format!("Failed to read '{}': {}", path.display(), error)),
+    };
+
+    parse_overrides(&contents).map_err(|message| format!("{}: {}", path.display(), message))
+}
+
+/// Parses the TOML of a theme file.
+fn parse_overrides(contents: &str) -> Result<ThemeOverrides, String> {
+    toml::from_str(contents).map_err(|error| error.to_string())
+}
+
+/// Represents the colours set in the theme file, which take the place of those of whichever
+/// theme is chosen.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeOverrides {
+    /// The colour behind the grid.
+    pub background_color: Option<[u8; 4]>,
+
+    /// The lighter of the two colours the tiles of the grid alternate between.
+    pub tile_color_light: Option<[u8; 4]>,
+
+    /// The darker of the two colours the tiles of the grid alternate between.
+    pub tile_color_dark: Option<[u8; 4]>,
+
+    /// The colour of the snake's head.
+    pub head_color: Option<[u8; 4]>,
+
+    /// The colour of the tail piece right behind the head.
+    pub tail_color_light: Option<[u8; 4]>,
+
+    /// The colour of the tip of the tail.
+    pub tail_color_dark: Option<[u8; 4]>,
+
+    /// The colour of the ordinary fruit.
+    pub fruit_color: Option<[u8; 4]>,
+}
+
+impl ThemeOverrides {
+    /// Sets the colours of `theme` that are overridden, keeping its name so that the themes
+    /// can still be switched through from it.
+    pub fn apply(&self, theme: Theme) -> Theme {
+        let color = |value: Option<[u8; 4]>, color: Color| {
+            value.map_or(color, |[r, g, b, a]| Color::from_rgba(r, g, b, a))
+        };
+
+        Theme {
+            background: color(self.background_color, theme.background),
+            tile_light: color(self.tile_color_light, theme.tile_light),
+            tile_dark: color(self.tile_color_dark, theme.tile_dark),
+            head: color(self.head_color, theme.head),
+            tail_light: color(self.tail_color_light, theme.tail_light),
+            tail_dark: color(self.tail_color_dark, theme.tail_dark),
+            fruit: color(self.fruit_color, theme.fruit),
+            ..theme
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEME_DARK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_colours_set_are_overridden() {
+        let overrides = parse_overrides(
+            "# My colours\nhead_color = [19, 138, 54, 255] # A brighter green\n\nfruit_color = [255, 0, 0, 255]\n",
+        )
+        .unwrap();
+
+        let theme = overrides.apply(THEME_LIGHT);
+
+        assert_eq!(theme.name, THEME_LIGHT.name);
+        assert_eq!(theme.head, Color::from_rgba(19, 138, 54, 255));
+        assert_eq!(theme.fruit, Color::from_rgba(255, 0, 0, 255));
+        assert_eq!(theme.background, THEME_LIGHT.background);
+        assert_eq!(theme.tail_dark, THEME_LIGHT.tail_dark);
+    }
+
+    #[test]
+    fn overridden_theme_switches_to_the_next_one() {
+        let overrides = ThemeOverrides {
+            head_color: Some([1, 2, 3, 255]),
+            ..ThemeOverrides::default()
+        };
+
+        let next = overrides.apply(overrides.apply(THEME_DARK).next());
+
+        assert_eq!(next.name, THEME_CLASSIC.name);
+        assert_eq!(next.head, Color::from_rgba(1, 2, 3, 255));
+        assert_eq!(next.background, THEME_CLASSIC.background);
+    }
+
+    #[test]
+    fn invalid_theme_files_are_rejected() {
+        assert!(parse_overrides("snake_color = [0, 0, 0, 255]").is_err());
+        assert!(parse_overrides("head_color = [0, 0, 256, 255]").is_err());
+        assert!(parse_overrides("head_color = [0, 0, 0]").is_err());
+    }
+}